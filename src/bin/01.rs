@@ -1,75 +1,13 @@
-use std::array::IntoIter;
+use advent_of_code::helpers::{parse::records, TopN};
 
 #[derive(Clone, Debug)]
 struct Backpack {
     fruits: Vec<usize>,
 }
-// hypothesis is the array is always sorted -> ascending order
-struct SortedArray<T, const N: usize>
-// Ideally Copy trait wouldn't be necessary
-where
-    T: Ord + Copy,
-{
-    v: [T; N],
-}
-
-impl<T: Ord + Copy, const N: usize> SortedArray<T, N> {
-    /// Insert `new_el` in the SortedArray if it fits there.  
-
-    /// If the `new_el` is lower than all the SortedArray,
-    /// the SortedArray will remain unchanged.
-    /// ```
-    /// let mut sa = SortedArray::from([2, 4, 7, 7]);
-    /// sa.insert(8);
-    /// assert_eq!(sa.v, [4, 7, 7, 8]);
-    ///     
-    /// sa.insert(5);
-    /// assert_eq!(sa.v, [5, 7, 7, 8]);
-    /// ```
-    pub fn insert(&mut self, new_el: T) {
-        let mut iter = self.v.iter_mut().peekable();
-        while let Some(cur_el) = iter.next() {
-            if new_el < *cur_el {
-                return;
-            }
-
-            if new_el > *cur_el {
-                match iter.peek_mut() {
-                    None => {
-                        // cur_el == last_el; cur_el < new_el
-                        *cur_el = new_el;
-                        return;
-                    }
-                    Some(next_el) => {
-                        // cur_el < new_el < next_el
-                        if new_el < **next_el {
-                            *cur_el = new_el;
-                            return;
-                        } else {
-                            // This is where copy trait is necessary
-                            *cur_el = **next_el;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    pub fn iter(&self) -> IntoIter<T, N> {
-        self.v.into_iter()
-    }
-}
-
-impl<T: Ord + Copy, const N: usize> From<[T; N]> for SortedArray<T, N> {
-    fn from(mut v: [T; N]) -> Self {
-        v.sort();
-        SortedArray { v }
-    }
-}
 
 impl Backpack {
     fn total(&self) -> usize {
-        return self.fruits.iter().sum();
+        self.fruits.iter().sum()
     }
 }
 
@@ -79,24 +17,14 @@ struct Crew {
 
 impl From<&str> for Crew {
     fn from(input: &str) -> Self {
-        let mut elves = vec![];
-        let mut fruits: Vec<usize> = vec![];
-        for line in input.lines() {
-            match line.trim() {
-                "" => {
-                    elves.push(Backpack {
-                        fruits: fruits.clone(),
-                    });
-                    fruits.clear();
-                }
-                clr => {
-                    fruits.push(clr.parse::<usize>().expect("Failed to parse input line"));
-                }
-            }
-        }
-        if !fruits.is_empty() {
-            elves.push(Backpack { fruits });
-        }
+        let elves = records(input)
+            .map(|record| Backpack {
+                fruits: record
+                    .lines()
+                    .map(|l| l.parse::<usize>().expect("Failed to parse input line"))
+                    .collect(),
+            })
+            .collect();
         Crew { elves }
     }
 }
@@ -128,11 +56,8 @@ impl Crew {
     // part_one => ~900µs
     // part_two => ~900µs
     fn best_n_backpacks<const N: usize>(&self) -> usize {
-        let mut sorted_array = SortedArray::from([0; N]);
-        self.elves
-            .iter()
-            .for_each(|el| sorted_array.insert(el.total()));
-        sorted_array.iter().sum()
+        let best: TopN<usize, N> = self.elves.iter().map(Backpack::total).collect();
+        best.iter().sum()
     }
 }
 
@@ -165,42 +90,4 @@ mod tests {
         let input = advent_of_code::read_file("examples", 1);
         assert_eq!(part_two(&input), Some(45000));
     }
-
-    #[test]
-    fn test_sorted_array_same_elems() {
-        let mut sa = SortedArray::from([3; 5]);
-        sa.insert(4);
-        assert_eq!(sa.v, [3, 3, 3, 3, 4]);
-    }
-
-    #[test]
-    fn test_sorted_array_replace_first() {
-        let mut sa = SortedArray::from([2, 4, 5]);
-        sa.insert(3);
-        assert_eq!(sa.v, [3, 4, 5]);
-    }
-
-    #[test]
-    fn test_sorted_array_replace_middle() {
-        let mut sa = SortedArray::from([2, 3, 5]);
-        sa.insert(4);
-        assert_eq!(sa.v, [3, 4, 5]);
-    }
-
-    #[test]
-    fn test_sorted_array_replace_last() {
-        let mut sa = SortedArray::from([2, 3, 5, 5]);
-        sa.insert(6);
-        assert_eq!(sa.v, [3, 5, 5, 6]);
-    }
-
-    #[test]
-    fn test_sorted_array_doc_test() {
-        let mut sa = SortedArray::from([2, 4, 7, 7]);
-        sa.insert(8);
-        assert_eq!(sa.v, [4, 7, 7, 8]);
-
-        sa.insert(5);
-        assert_eq!(sa.v, [5, 7, 7, 8]);
-    }
 }
@@ -1,12 +1,78 @@
-#![feature(iter_array_chunks)]
+use std::{
+    fmt::{Display, Write},
+    thread,
+    time::Duration,
+};
+
+use advent_of_code::ANSI_CLEAR;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, satisfy, space0, space1},
+    combinator::{map, map_res},
+    multi::separated_list1,
+    sequence::{delimited, terminated},
+    IResult,
+};
+
+/// How a crane moves crates between stacks.
+trait CraneModel {
+    /// Moves `n` crates from the top of `src` to the top of `dst`.
+    fn move_crates(&self, n: usize, src: &mut CrateStack, dst: &mut CrateStack);
+}
+
+/// Moves crates one at a time.
+struct Crane9000;
+
+impl CraneModel for Crane9000 {
+    fn move_crates(&self, n: usize, src: &mut CrateStack, dst: &mut CrateStack) {
+        for _ in 0..n {
+            let c = src
+                .pop()
+                .expect("Couldn't pop out of stack, might be empty");
+            dst.push(c);
+        }
+    }
+}
 
-use std::fmt::{Display, Write};
+/// Moves all crates at once, keeping their order.
+struct Crane9001;
+
+impl CraneModel for Crane9001 {
+    fn move_crates(&self, n: usize, src: &mut CrateStack, dst: &mut CrateStack) {
+        let v = src
+            .popn(n)
+            .expect("Couldn't popn out of stack, might be empty");
+        dst.pushn(v);
+    }
+}
+
+/// Moves at most `self.0` crates at once, keeping their order within a batch.
+struct BatchCrane(usize);
+
+impl CraneModel for BatchCrane {
+    fn move_crates(&self, mut n: usize, src: &mut CrateStack, dst: &mut CrateStack) {
+        while n > 0 {
+            let batch = n.min(self.0);
+            Crane9001.move_crates(batch, src, dst);
+            n -= batch;
+        }
+    }
+}
 
-enum CargoCraneModel {
-    Crane9000,
-    Crane9001,
+/// `9000`, `9001` or `batch:<k>`.
+fn parse_model(name: &str) -> Option<Box<dyn CraneModel>> {
+    match name {
+        "9000" => Some(Box::new(Crane9000)),
+        "9001" => Some(Box::new(Crane9001)),
+        _ => {
+            let k = name.strip_prefix("batch:")?.parse().ok()?;
+            (k > 0).then(|| Box::new(BatchCrane(k)) as Box<dyn CraneModel>)
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy)]
 struct Instruction {
     n: usize,
     src: usize,
@@ -19,6 +85,18 @@ impl Instruction {
     }
 }
 
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.n,
+            self.src + 1,
+            self.dst + 1
+        )
+    }
+}
+
 #[derive(Debug)]
 struct CrateStack {
     crates: Vec<char>,
@@ -53,10 +131,6 @@ impl CrateStack {
         Some(res)
     }
 
-    fn push_back(&mut self, c: char) {
-        self.crates.insert(0, c);
-    }
-
     fn peek(&self) -> Option<&char> {
         self.crates.last()
     }
@@ -70,9 +144,212 @@ impl CrateStack {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum ParseErrorKind {
+    /// A drawing cell that is neither `[X]` nor blank.
+    InvalidCrate,
+    /// A drawing line with more stacks than labels.
+    RaggedLine {
+        labels: usize,
+    },
+    /// The line under the drawing does not label the stacks.
+    MissingLabels,
+    WrongLabel {
+        expected: usize,
+    },
+    InvalidMove,
+    UnknownStack {
+        stack: usize,
+        labels: usize,
+    },
+    EmptyStack {
+        stack: usize,
+        needed: usize,
+        available: usize,
+    },
+}
+
+/// Error in the puzzle input, `line` and `col` start at 1.
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    line: usize,
+    col: usize,
+    kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.col)?;
+        match &self.kind {
+            ParseErrorKind::InvalidCrate => write!(f, "expected a crate like [A] or a blank"),
+            ParseErrorKind::RaggedLine { labels } => {
+                write!(f, "crate outside of the {labels} labelled stacks")
+            }
+            ParseErrorKind::MissingLabels => write!(f, "expected the stack labels"),
+            ParseErrorKind::WrongLabel { expected } => write!(f, "expected label {expected}"),
+            ParseErrorKind::InvalidMove => write!(f, "expected `move <n> from <src> to <dst>`"),
+            ParseErrorKind::UnknownStack { stack, labels } => {
+                write!(f, "stack {stack} does not exist, stacks are 1 to {labels}")
+            }
+            ParseErrorKind::EmptyStack {
+                stack,
+                needed,
+                available,
+            } => write!(
+                f,
+                "cannot take {needed} crates from stack {stack}, it only has {available}"
+            ),
+        }
+    }
+}
+
+/// Column of the start of `part`, a slice of `line`.
+fn col(line: &str, part: &str) -> usize {
+    part.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+fn crate_cell(input: &str) -> IResult<&str, Option<char>> {
+    alt((
+        map(
+            delimited(char('['), satisfy(|c| c.is_ascii_alphabetic()), char(']')),
+            Some,
+        ),
+        map(tag("   "), |_| None),
+    ))(input)
+}
+
+/// Crates of one drawing line, `None` for blanks.
+fn crate_row(input: &str) -> IResult<&str, Vec<Option<char>>> {
+    terminated(separated_list1(char(' '), crate_cell), space0)(input)
+}
+
+fn labels(input: &str) -> IResult<&str, Vec<&str>> {
+    delimited(space0, separated_list1(space1, digit1), space0)(input)
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// Stacks from the drawing, bottom crate first, and the moves.
+fn parse_input(input: &str) -> Result<(Vec<CrateStack>, Vec<Instruction>), ParseError> {
+    let mut lines = input.lines().enumerate().map(|(i, l)| (i + 1, l));
+    let error = |line, col, kind| ParseError { line, col, kind };
+
+    // drawing, top row first, up to the labels line
+    let mut rows = Vec::new();
+    let n_stacks = loop {
+        let Some((line_no, line)) = lines.next() else {
+            return Err(error(
+                input.lines().count() + 1,
+                1,
+                ParseErrorKind::MissingLabels,
+            ));
+        };
+        match crate_row(line) {
+            Ok(("", row)) => {
+                rows.push((line_no, row));
+                continue;
+            }
+            Ok((rest, _)) => {
+                return Err(error(
+                    line_no,
+                    col(line, rest),
+                    ParseErrorKind::InvalidCrate,
+                ))
+            }
+            Err(_) => {}
+        }
+        let Ok(("", found)) = labels(line) else {
+            let kind = if line.trim().is_empty() || line.starts_with("move") {
+                ParseErrorKind::MissingLabels
+            } else {
+                ParseErrorKind::InvalidCrate
+            };
+            return Err(error(line_no, 1, kind));
+        };
+        for (i, label) in found.iter().enumerate() {
+            if label.parse() != Ok(i + 1) {
+                return Err(error(
+                    line_no,
+                    col(line, label),
+                    ParseErrorKind::WrongLabel { expected: i + 1 },
+                ));
+            }
+        }
+        break found.len();
+    };
+
+    let mut stacks: Vec<CrateStack> = (0..n_stacks).map(|_| CrateStack::new()).collect();
+    for (line_no, row) in rows.into_iter().rev() {
+        if row.len() > n_stacks {
+            return Err(error(
+                line_no,
+                4 * n_stacks + 1,
+                ParseErrorKind::RaggedLine { labels: n_stacks },
+            ));
+        }
+        for (stack, c) in stacks.iter_mut().zip(row) {
+            if let Some(c) = c {
+                stack.push(c);
+            }
+        }
+    }
+
+    // moves, checked against the number of crates left on each stack
+    let mut heights: Vec<usize> = stacks.iter().map(CrateStack::len).collect();
+    let mut instructions = Vec::new();
+    for (line_no, line) in lines.filter(|(_, l)| !l.trim().is_empty()) {
+        let invalid = |rest: &str| error(line_no, col(line, rest), ParseErrorKind::InvalidMove);
+        let to_invalid = |err: nom::Err<nom::error::Error<&str>>| match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => invalid(e.input),
+            nom::Err::Incomplete(_) => invalid(""),
+        };
+        let (rest, _) = tag("move ")(line).map_err(to_invalid)?;
+        let n_col = col(line, rest);
+        let (rest, n) = number(rest).map_err(to_invalid)?;
+        let (rest, _) = tag(" from ")(rest).map_err(to_invalid)?;
+        let src_col = col(line, rest);
+        let (rest, src) = number(rest).map_err(to_invalid)?;
+        let (rest, _) = tag(" to ")(rest).map_err(to_invalid)?;
+        let dst_col = col(line, rest);
+        let (rest, dst) = number(rest).map_err(to_invalid)?;
+        if !rest.trim_end().is_empty() {
+            return Err(invalid(rest));
+        }
+        for (stack, stack_col) in [(src, src_col), (dst, dst_col)] {
+            if !(1..=n_stacks).contains(&stack) {
+                return Err(error(
+                    line_no,
+                    stack_col,
+                    ParseErrorKind::UnknownStack {
+                        stack,
+                        labels: n_stacks,
+                    },
+                ));
+            }
+        }
+        let (src, dst) = (src - 1, dst - 1);
+        if heights[src] < n {
+            return Err(error(
+                line_no,
+                n_col,
+                ParseErrorKind::EmptyStack {
+                    stack: src + 1,
+                    needed: n,
+                    available: heights[src],
+                },
+            ));
+        }
+        heights[src] -= n;
+        heights[dst] += n;
+        instructions.push(Instruction { n, src, dst });
+    }
+    Ok((stacks, instructions))
+}
+
 struct CargoCrane {
-    model: CargoCraneModel,
-    crates_parsed: bool,
+    model: Box<dyn CraneModel>,
     stacks: Vec<CrateStack>,
     instructions: Vec<Instruction>,
 }
@@ -91,7 +368,7 @@ impl Display for CargoCrane {
             });
             f.write_char('\n').ok();
         }
-        for i in 0..self.stacks.len() {
+        for i in 1..=self.stacks.len() {
             f.write_str(&format!(" {i}  "))
                 .expect("Failed write_str in CargoCrane::Display");
         }
@@ -100,102 +377,36 @@ impl Display for CargoCrane {
 }
 
 impl CargoCrane {
-    fn new(lines: &str, model: CargoCraneModel) -> Self {
-        let mut cargo_crane = CargoCrane {
-            model,
-            crates_parsed: false,
-            instructions: vec![],
-            stacks: vec![],
-        };
-        cargo_crane.parse_lines(lines);
-        cargo_crane
+    fn new(lines: &str, model: impl CraneModel + 'static) -> Result<Self, ParseError> {
+        Self::with_model(lines, Box::new(model))
     }
 
-    fn parse_crates(&mut self, line: &str) {
-        line.chars()
-            .skip(1)
-            .step_by(4)
-            .enumerate()
-            .for_each(|(i, c)| {
-                while i >= self.stacks.len() {
-                    self.stacks.push(CrateStack::new());
-                }
-                if c != ' ' {
-                    self.stacks[i].push_back(c);
-                }
-            });
-    }
-
-    fn parse_move(&mut self, line: &str) {
-        match line.split(' ').collect::<Vec<&str>>()[..] {
-            ["move", n, "from", src, "to", dst] => self.instructions.insert(
-                0,
-                Instruction {
-                    n: n.parse::<usize>()
-                        .expect("Failed parsing int to Instruction."),
-                    src: src
-                        .parse::<usize>()
-                        .expect("Failed parsing int to Instruction.")
-                        - 1,
-                    dst: dst
-                        .parse::<usize>()
-                        .expect("Failed parsing int to Instruction.")
-                        - 1,
-                },
-            ),
-            _ => panic!("Failed parsing line: \"{line}\""),
-        }
+    fn with_model(lines: &str, model: Box<dyn CraneModel>) -> Result<Self, ParseError> {
+        let (stacks, mut instructions) = parse_input(lines)?;
+        instructions.reverse();
+        Ok(CargoCrane {
+            model,
+            instructions,
+            stacks,
+        })
     }
 
-    fn parse_lines(&mut self, lines: &str) {
-        let lines = lines.lines();
-        lines.for_each(|l: &str| {
-            if l.split(' ').all(|c| "0123456789".contains(c)) || l.trim().is_empty() {
-                self.crates_parsed = true;
-            } else if !self.crates_parsed {
-                self.parse_crates(l);
+    /// Runs the next instruction and returns it.
+    fn step_solve(&mut self) -> Instruction {
+        let instr = self.instructions.pop().expect("Failed to pop instruction");
+        let (n, src, dst) = instr.destructure();
+        assert!(src < self.stacks.len(), "Failed to get src stack");
+        assert!(dst < self.stacks.len(), "Failed to get dst stack");
+        if src != dst {
+            let (low, high) = self.stacks.split_at_mut(src.max(dst));
+            let (src, dst) = if src < dst {
+                (&mut low[src], &mut high[0])
             } else {
-                self.parse_move(l);
-            }
-        })
-    }
-    fn step_solve(&mut self) {
-        let (n, src, dst) = self
-            .instructions
-            .pop()
-            .expect("Failed to pop instruction")
-            .destructure();
-        // println!("move {n} from {src} to {dst}");
-
-        match self.model {
-            CargoCraneModel::Crane9000 => {
-                for _ in 0..n {
-                    let c = self
-                        .stacks
-                        .get_mut(src)
-                        .expect("Failed to get src stack")
-                        .pop()
-                        .expect("Couldn't pop out of stack, might be empty");
-
-                    self.stacks
-                        .get_mut(dst)
-                        .expect("Failed to get dst stack")
-                        .push(c);
-                }
-            }
-            CargoCraneModel::Crane9001 => {
-                let v = self
-                    .stacks
-                    .get_mut(src)
-                    .expect("Failed to get src stack")
-                    .popn(n)
-                    .expect("Couldn't popn out of stack, might be empty");
-                self.stacks
-                    .get_mut(dst)
-                    .expect("Failed to get dst stack")
-                    .pushn(v);
-            }
+                (&mut high[0], &mut low[dst])
+            };
+            self.model.move_crates(n, src, dst);
         }
+        instr
     }
 
     #[inline]
@@ -212,35 +423,51 @@ impl CargoCrane {
     }
 }
 
-// ~1.95ms
-pub fn part_one(input: &str) -> Option<String> {
-    let mut crane = CargoCrane::new(input, CargoCraneModel::Crane9000);
-
+fn solve(input: &str, model: impl CraneModel + 'static) -> String {
+    let mut crane = CargoCrane::new(input, model).unwrap_or_else(|err| panic!("{err}"));
     while !crane.is_solved() {
-        // println!("{}\n", crane);
         crane.step_solve();
-        // println!("{}\n\n", crane);
     }
+    crane.get_sol()
+}
 
-    Some(crane.get_sol())
+// ~1.95ms
+pub fn part_one(input: &str) -> Option<String> {
+    Some(solve(input, Crane9000))
 }
 // ~2.05ms
 pub fn part_two(input: &str) -> Option<String> {
-    let mut crane = CargoCrane::new(input, CargoCraneModel::Crane9001);
+    Some(solve(input, Crane9001))
+}
 
+/// Renders the stacks before the first and after every instruction.
+fn animate(input: &str, model: Box<dyn CraneModel>, delay: Duration) {
+    let mut crane = CargoCrane::with_model(input, model).unwrap_or_else(|err| panic!("{err}"));
+    let total = crane.instructions.len();
+    println!("{ANSI_CLEAR}step 0/{total}\n{crane}");
     while !crane.is_solved() {
-        // println!("{}\n", crane);
-        crane.step_solve();
-        // println!("{}\n\n", crane);
+        thread::sleep(delay);
+        let instr = crane.step_solve();
+        let step = total - crane.instructions.len();
+        println!("{ANSI_CLEAR}step {step}/{total}: {instr}\n{crane}");
     }
-
-    Some(crane.get_sol())
+    println!("\ntop crates: {}", crane.get_sol());
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 5);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    if args.contains("--animate") {
+        let model: String = args
+            .opt_value_from_str("--model")
+            .unwrap()
+            .unwrap_or_else(|| "9000".to_string());
+        let model = parse_model(&model).expect("Unknown model, use 9000, 9001 or batch:<k>");
+        let delay = advent_of_code::opt_arg_in(&mut args, "--delay", 0..).unwrap_or(200);
+        animate(input, model, Duration::from_millis(delay));
+    }
 }
 
 #[cfg(test)]
@@ -253,6 +480,86 @@ mod tests {
         assert_eq!(part_one(&input), Some(String::from("CMZ")));
     }
 
+    #[test]
+    fn test_models() {
+        let input = advent_of_code::read_file("examples", 5);
+        assert_eq!(solve(&input, BatchCrane(1)), "CMZ");
+        assert_eq!(solve(&input, BatchCrane(3)), "MCD");
+        // moving Z N D two at a time stacks N D then Z
+        assert_eq!(solve(&input, BatchCrane(2)), "MCZ");
+        assert!(parse_model("batch:0").is_none());
+        assert!(parse_model("9002").is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::read_file("examples", 5);
+        let mut crane = CargoCrane::new(&input, Crane9001).unwrap();
+        assert_eq!(crane.step_solve().to_string(), "move 1 from 2 to 1");
+        assert_eq!(
+            crane.to_string(),
+            "[D]         \n[N] [C]     \n[Z] [M] [P] \n 1   2   3  "
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = |input: &str| {
+            let err = parse_input(input).unwrap_err();
+            (err.line, err.col, err.kind)
+        };
+        let drawing = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\n";
+        assert!(parse_input(drawing).is_ok());
+        assert_eq!(
+            err("[N] [C]    \n[Z] [M] [P] [Q]\n 1   2   3 \n"),
+            (2, 13, ParseErrorKind::RaggedLine { labels: 3 })
+        );
+        assert_eq!(
+            err("[N] [C]\n[Z] [M  [P]\n 1   2   3 \n"),
+            (2, 5, ParseErrorKind::InvalidCrate)
+        );
+        assert_eq!(
+            err("[N] [C]\n\nmove 1 from 1 to 2"),
+            (2, 1, ParseErrorKind::MissingLabels)
+        );
+        assert_eq!(
+            err("[N] [C]\n 1   3 \n"),
+            (2, 6, ParseErrorKind::WrongLabel { expected: 2 })
+        );
+        assert_eq!(
+            err(&format!("{drawing}move 1 from 2 to 1\nmove 1 frm 1 to 2")),
+            (7, 7, ParseErrorKind::InvalidMove)
+        );
+        assert_eq!(
+            err(&format!("{drawing}move 1 from 4 to 1")),
+            (
+                6,
+                13,
+                ParseErrorKind::UnknownStack {
+                    stack: 4,
+                    labels: 3
+                }
+            )
+        );
+        assert_eq!(
+            err(&format!("{drawing}move 2 from 3 to 1\n")),
+            (
+                6,
+                6,
+                ParseErrorKind::EmptyStack {
+                    stack: 3,
+                    needed: 2,
+                    available: 1
+                }
+            )
+        );
+        let error = parse_input(&format!("{drawing}move 1 from 2 to 0")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 6, column 18: stack 0 does not exist, stacks are 1 to 3"
+        );
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 5);
@@ -1,5 +1,3 @@
-#![feature(iter_array_chunks)]
-
 /*
 (debug binary)
 🎄 Part 1 🎄
@@ -13,130 +11,131 @@
 🎄 Part 2 🎄
 2447 (elapsed: 251.14µs)
 */
-trait Unicity {
-    fn all_unique(&self) -> bool;
-}
+use std::{
+    convert::Infallible,
+    fs::File,
+    io::{self, BufReader, Read},
+};
 
-impl Unicity for [char; 3] {
-    fn all_unique(&self) -> bool {
-        self[0] != self[1] && self[1] != self[2] && self[2] != self[0]
-    }
-}
+use advent_of_code::helpers::{rng::Lcg, DistinctWindow};
 
-#[derive(Debug)]
-struct Similaritude {
-    pub i1: usize,
-    pub i2: usize,
+/// A marker of the signal, with the number of bytes read up to its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    StartOfPacket(usize),
+    StartOfMessage(usize),
 }
 
-struct UnicityTracker<T, const N: usize>
-where
-    T: PartialEq,
-{
-    sims: Option<Vec<Similaritude>>,
-    data: [T; N],
+/// Marker detection state, fed one byte at a time.
+#[derive(Debug, Default)]
+struct Detector {
+    packet: DistinctWindow<4>,
+    message: DistinctWindow<14>,
+    packet_found: bool,
+    /// Message marker ending on the same byte as the packet marker.
+    pending: Option<Marker>,
 }
 
-impl<T: PartialEq, const N: usize> UnicityTracker<T, N> {
-    fn new(data: [T; N]) -> Self {
-        let mut sims = vec![];
-        for (i1, e1) in data.iter().enumerate() {
-            for (i2, e2) in data.iter().enumerate() {
-                if *e1 == *e2 && i1 != i2 {
-                    sims.push(Similaritude { i1, i2 })
-                }
-            }
+impl Detector {
+    /// Reads `bytes` up to the end of the next marker, or up to the first error.
+    /// Reads nothing more once both markers have been found.
+    fn next_in<E>(
+        &mut self,
+        bytes: impl Iterator<Item = Result<u8, E>>,
+    ) -> Option<Result<Marker, E>> {
+        if let Some(marker) = self.pending.take() {
+            return Some(Ok(marker));
         }
-        UnicityTracker {
-            data,
-            sims: Some(sims),
+        // a message marker is also a packet marker, so it always comes last
+        if self.message.is_distinct() {
+            return None;
         }
+        for byte in bytes {
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(err) => return Some(Err(err)),
+            };
+            let packet = !self.packet_found && self.packet.push(byte);
+            let message = self
+                .message
+                .push(byte)
+                .then(|| Marker::StartOfMessage(self.message.pushed()));
+            if packet {
+                self.packet_found = true;
+                self.pending = message;
+                return Some(Ok(Marker::StartOfPacket(self.packet.pushed())));
+            }
+            if message.is_some() {
+                return message.map(Ok);
+            }
+        }
+        None
     }
+}
 
-    fn get_data(&self) -> &[T; N] {
-        &self.data
-    }
-
-    fn set(&mut self, i: usize, e: T) {
-        let mut next_sims = vec![];
-
-        if e == self.data[i] {
-            return;
-        }
+/// Markers of a byte iterator, each yielded as soon as its last byte is read.
+struct Markers<I> {
+    bytes: I,
+    detector: Detector,
+}
 
-        // add new similaritudes
-        for (i1, e1) in self.data.iter().enumerate() {
-            if e == *e1 && i1 != i {
-                next_sims.push(Similaritude { i1, i2: i });
-            }
+impl<I: Iterator<Item = u8>> Markers<I> {
+    fn new(bytes: impl IntoIterator<IntoIter = I>) -> Self {
+        Markers {
+            bytes: bytes.into_iter(),
+            detector: Detector::default(),
         }
+    }
+}
 
-        // remove sims that don't need to be there
-        let sims = self.sims.take();
-        self.sims = None;
-
-        self.sims = Some(
-            sims.unwrap()
-                .into_iter()
-                .filter(|s| {
-                    if s.i1 == i && self.data[s.i2] != e {
-                        return false;
-                    }
-
-                    if s.i2 == i && self.data[s.i1] != e {
-                        return false;
-                    }
-
-                    true
-                })
-                .collect(),
-        );
+impl<I: Iterator<Item = u8>> Iterator for Markers<I> {
+    type Item = Marker;
 
-        self.data[i] = e;
-        self.sims.as_mut().unwrap().append(&mut next_sims);
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.bytes.by_ref().map(Ok::<u8, Infallible>);
+        self.detector.next_in(bytes).map(|marker| match marker {
+            Ok(marker) => marker,
+            Err(never) => match never {},
+        })
     }
 }
 
-impl<T: PartialEq, const N: usize> Unicity for UnicityTracker<T, N> {
-    // Hypothesis -> all similaritudes are noted
-    fn all_unique(&self) -> bool {
-        self.sims.as_ref().unwrap().is_empty()
-    }
+/// Markers of a reader, stopping after the first read error.
+struct ReadMarkers<R> {
+    bytes: io::Bytes<BufReader<R>>,
+    detector: Detector,
+    failed: bool,
 }
 
-fn start_marker_count(line: &str) -> Option<u32> {
-    assert!(line.len() > 3);
-    let mut old: [char; 3] = [
-        line.chars().next().unwrap(),
-        line.chars().nth(1).unwrap(),
-        line.chars().nth(2).unwrap(),
-    ];
-    let mut i = 0;
-
-    for (count, c) in line.chars().enumerate().skip(3) {
-        if !old.contains(&c) && old.all_unique() {
-            return Some((count + 1) as u32); // + 1 => 1-indexed
-        } else {
-            old[i] = c;
-            i = (i + 1) % 3;
+impl<R: Read> ReadMarkers<R> {
+    fn new(reader: R) -> Self {
+        ReadMarkers {
+            bytes: BufReader::new(reader).bytes(),
+            detector: Detector::default(),
+            failed: false,
         }
     }
-    None
 }
 
-fn message_marker_count(line: &str) -> Option<u32> {
-    let data = line.chars().array_chunks::<13>().next().unwrap();
-    let mut ut = UnicityTracker::new(data);
-    let mut i = 0;
-    for (count, c) in line.chars().enumerate().skip(13) {
-        if !ut.get_data().contains(&c) && ut.all_unique() {
-            return Some((count + 1) as u32);
-        } else {
-            ut.set(i, c);
-            i = (i + 1) % 13;
+impl<R: Read> Iterator for ReadMarkers<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
         }
+        let marker = self.detector.next_in(&mut self.bytes);
+        self.failed = matches!(marker, Some(Err(_)));
+        marker
     }
-    None
+}
+
+fn start_marker_count(line: &str) -> Option<u32> {
+    DistinctWindow::<4>::find_in(line.bytes()).map(|count| count as u32)
+}
+
+fn message_marker_count(line: &str) -> Option<u32> {
+    DistinctWindow::<14>::find_in(line.bytes()).map(|count| count as u32)
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -148,9 +147,36 @@ pub fn part_two(input: &str) -> Option<u32> {
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 6);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    // `-` reads the signal from stdin
+    let stream: Option<String> = args.opt_value_from_str("--stream").unwrap();
+    if let Some(path) = stream {
+        let reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(File::open(&path).expect("Could not open signal"))
+        };
+        for marker in ReadMarkers::new(reader) {
+            match marker {
+                Ok(marker) => println!("{marker:?}"),
+                Err(err) => eprintln!("Could not read signal: {err}"),
+            }
+        }
+    }
+    // a pseudo-random signal of that many letters, generated as it is read
+    let generate: Option<usize> = advent_of_code::opt_arg_in(&mut args, "--generate", 0..);
+    if let Some(len) = generate {
+        let seed = advent_of_code::opt_arg_in(&mut args, "--seed", 0..).unwrap_or(0);
+        let alphabet = advent_of_code::opt_arg_in(&mut args, "--alphabet", 1..=26).unwrap_or(14);
+        let mut rng = Lcg::new(seed);
+        let signal = (0..len).map(|_| b'a' + rng.below(alphabet) as u8);
+        for marker in Markers::new(signal) {
+            println!("{marker:?}");
+        }
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +195,62 @@ mod tests {
         assert_eq!(part_two(&input), Some(23));
     }
 
+    #[test]
+    fn test_stream_markers() {
+        // a long signal is only read up to its message marker
+        let mut read = 0;
+        let signal = b"abab"
+            .iter()
+            .copied()
+            .cycle()
+            .take(1_000_000)
+            .chain(*b"cdefghijklmnopqrstuvwxyz")
+            .chain(std::iter::repeat(b'z'))
+            .inspect(|_| read += 1);
+        let markers: Vec<Marker> = Markers::new(signal).collect();
+        assert_eq!(
+            markers,
+            [
+                Marker::StartOfPacket(1_000_002),
+                Marker::StartOfMessage(1_000_012)
+            ]
+        );
+        assert_eq!(read, 1_000_012);
+    }
+
+    #[test]
+    fn test_stream_same_byte() {
+        let markers: Vec<Marker> = Markers::new(*b"abcdefghijklmnop").collect();
+        assert_eq!(
+            markers,
+            [Marker::StartOfPacket(4), Marker::StartOfMessage(14)]
+        );
+        let markers: Vec<Marker> = ReadMarkers::new(&b"aaaabcdefghijklmnop"[..])
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            markers,
+            [Marker::StartOfPacket(7), Marker::StartOfMessage(17)]
+        );
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn test_stream_error() {
+        let reader = (&b"abc"[..]).chain(Broken);
+        let mut markers = ReadMarkers::new(reader);
+        let err = markers.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "broken");
+        assert!(markers.next().is_none());
+    }
+
     #[test]
     fn test_start_marker_count() {
         for (marker, count) in [
@@ -177,7 +259,7 @@ mod tests {
             ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10),
             ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11),
         ] {
-            assert_eq!(start_marker_count(&marker), Some(count));
+            assert_eq!(start_marker_count(marker), Some(count));
         }
     }
 
@@ -191,7 +273,7 @@ mod tests {
             ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 29),
             ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 26),
         ] {
-            assert_eq!(message_marker_count(&marker), Some(count));
+            assert_eq!(message_marker_count(marker), Some(count));
         }
     }
 }
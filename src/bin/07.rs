@@ -12,188 +12,348 @@
 13210366 (elapsed: 224.72µs)
 */
 
-use std::collections::HashMap;
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Display};
 
-enum Cmd {
+use advent_of_code::Params;
+
+enum Cmd<'a> {
     Ls,
-    Pushd(String),
-    Popd,
+    Cd(&'a str),
 }
 
-struct File {
-    size: usize,
+/// Transcript line that does not match the file system reconstructed so far.
+#[derive(Debug, PartialEq, Eq)]
+enum FsError {
+    UnknownCommand {
+        line: usize,
+        cmd: String,
+    },
+    InvalidEntry {
+        line: usize,
+        entry: String,
+    },
+    /// Listing output that does not follow an `ls`.
+    OutputWithoutLs {
+        line: usize,
+    },
+    /// `cd` into a directory that was not listed before.
+    UnknownDir {
+        line: usize,
+        path: String,
+    },
+    /// An entry listed twice with a different type or size.
+    Conflict {
+        line: usize,
+        path: String,
+    },
 }
 
+impl Display for FsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FsError::UnknownCommand { line, cmd } => {
+                write!(f, "line {line}: unknown command {cmd:?}")
+            }
+            FsError::InvalidEntry { line, entry } => {
+                write!(f, "line {line}: invalid entry {entry:?}")
+            }
+            FsError::OutputWithoutLs { line } => write!(f, "line {line}: output without ls"),
+            FsError::UnknownDir { line, path } => write!(f, "line {line}: no directory {path}"),
+            FsError::Conflict { line, path } => {
+                write!(f, "line {line}: {path} listed twice differently")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
 struct Dir {
-    files: HashMap<String, File>,
-    dirs: HashMap<String, Dir>,
+    files: BTreeMap<String, usize>,
+    dirs: BTreeMap<String, Dir>,
 }
 
 impl Dir {
-    fn new() -> Self {
-        Dir {
-            dirs: HashMap::new(),
-            files: HashMap::new(),
-        }
-    }
-
     fn total_size(&self) -> usize {
-        self.files.iter().fold(0, |acc, (_key, f)| acc + f.size)
-            + self
-                .dirs
-                .iter()
-                .fold(0, |acc, (_key, d)| acc + d.total_size())
-    }
-
-    fn get_sub_dir(&mut self, dir_path: &Vec<String>) -> &mut Dir {
-        let mut cur_dir = self;
-        for p in dir_path {
-            if cur_dir.dirs.contains_key(p) {
-                cur_dir = cur_dir.dirs.get_mut(p).unwrap();
-            }
-        }
-        cur_dir
+        self.files.values().sum::<usize>() + self.dirs.values().map(Dir::total_size).sum::<usize>()
     }
 
-    fn add_file(&mut self, (fname, f): (String, File)) {
-        self.files.insert(fname, f);
+    fn get(&self, path: &[String]) -> Option<&Dir> {
+        path.iter().try_fold(self, |dir, name| dir.dirs.get(name))
     }
 
-    fn add_dir(&mut self, (dname, d): (String, Dir)) {
-        self.dirs.insert(dname, d);
+    fn get_mut(&mut self, path: &[String]) -> Option<&mut Dir> {
+        path.iter()
+            .try_fold(self, |dir, name| dir.dirs.get_mut(name))
     }
 
-    fn push_file(&mut self, dir_path: &Vec<String>, file: (String, File)) {
-        let target_dir = self.get_sub_dir(dir_path);
-        target_dir.add_file(file);
+    /// Appends this directory and every directory below it, parents first,
+    /// and returns the size of this one.
+    fn walk_into<'a>(&'a self, path: String, out: &mut Vec<DirEntry<'a>>) -> usize {
+        let index = out.len();
+        let sub_paths: Vec<String> = self.dirs.keys().map(|name| join(&path, name)).collect();
+        out.push(DirEntry {
+            path,
+            size: 0,
+            dir: self,
+        });
+        let sub_dirs_size: usize = self
+            .dirs
+            .values()
+            .zip(sub_paths)
+            .map(|(dir, path)| dir.walk_into(path, out))
+            .sum();
+        let size = self.files.values().sum::<usize>() + sub_dirs_size;
+        out[index].size = size;
+        size
     }
 
-    fn push_dir(&mut self, dir_path: &Vec<String>, dir: (String, Dir)) {
-        let target_dir = self.get_sub_dir(dir_path);
-        target_dir.add_dir(dir);
+    fn tree_into(&self, name: &str, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{indent}- {name} (dir, size={})\n",
+            self.total_size()
+        ));
+        for (name, dir) in &self.dirs {
+            dir.tree_into(name, depth + 1, out);
+        }
+        for (name, size) in &self.files {
+            out.push_str(&format!("{indent}  - {name} (file, size={size})\n"));
+        }
     }
+}
 
-    fn list_sub_dirs(&self) -> Vec<&Dir> {
-        self.dirs
-            .iter()
-            .flat_map(|(_k, d)| {
-                let mut subds = d.list_sub_dirs();
-                subds.insert(0, d);
-                subds
-            })
-            .collect()
-    }
+/// A directory found by a query.
+#[derive(Debug)]
+struct DirEntry<'a> {
+    path: String,
+    /// Total size, including sub-directories.
+    size: usize,
+    dir: &'a Dir,
 }
 
-fn parse_command(cmd: &str) -> Cmd {
-    match cmd.strip_prefix("$ ").unwrap().trim() {
-        "ls" => Cmd::Ls,
-        cmd => match cmd.split_once(' ') {
-            Some(("cd", "..")) => Cmd::Popd,
-            Some(("cd", d)) => Cmd::Pushd(String::from(d)),
-            _t => {
-                println!("{_t:?} ??");
-                panic!("Failed parsing command !");
-            }
-        },
+fn join(path: &str, name: &str) -> String {
+    if path == "/" {
+        format!("/{name}")
+    } else {
+        format!("{path}/{name}")
     }
 }
 
-fn parse_file(file: &str) -> (String, File) {
-    if let Some((size_str, name)) = file.trim().split_once(' ') {
-        return (
-            String::from(name),
-            File {
-                size: size_str.parse::<usize>().unwrap(),
-            },
-        );
-    }
-    panic!("Failed to parse file!");
+/// File system reconstructed by replaying a transcript of `cd` and `ls` commands.
+#[derive(Debug, Default)]
+struct VirtualFs {
+    root: Dir,
+    cwd: Vec<String>,
+    listing: bool,
 }
 
-fn parse_dir(dir: &str) -> (String, Dir) {
-    if let Some((_dir, name)) = dir.trim().split_once(' ') {
-        return (String::from(name), Dir::new());
+impl VirtualFs {
+    fn replay(input: &str) -> Result<Self, FsError> {
+        let mut fs = VirtualFs::default();
+        for (index, line) in input.lines().enumerate() {
+            fs.replay_line(index + 1, line)?;
+        }
+        Ok(fs)
     }
-    panic!("Failed to parse dir!")
-}
 
-struct CliState {
-    path: Vec<String>,
-    root_dir: Dir,
-}
+    fn cwd_path(&self) -> String {
+        format!("/{}", self.cwd.join("/"))
+    }
 
-impl CliState {
-    fn new() -> Self {
-        CliState {
-            path: Vec::new(),
-            root_dir: Dir::new(),
+    fn replay_line(&mut self, line: usize, content: &str) -> Result<(), FsError> {
+        if let Some(cmd) = content.strip_prefix("$ ") {
+            self.listing = false;
+            match parse_command(cmd.trim()) {
+                Some(Cmd::Ls) => self.listing = true,
+                Some(Cmd::Cd(target)) => self.cd(line, target)?,
+                None => {
+                    return Err(FsError::UnknownCommand {
+                        line,
+                        cmd: cmd.to_string(),
+                    })
+                }
+            }
+            return Ok(());
+        }
+        if !self.listing {
+            return Err(FsError::OutputWithoutLs { line });
         }
+        let invalid = || FsError::InvalidEntry {
+            line,
+            entry: content.to_string(),
+        };
+        let (kind, name) = content.trim().split_once(' ').ok_or_else(invalid)?;
+        let path = join(&self.cwd_path(), name);
+        let conflict = || FsError::Conflict {
+            line,
+            path: path.clone(),
+        };
+        let cwd = self.root.get_mut(&self.cwd).expect("cwd always exists");
+        if kind == "dir" {
+            if cwd.files.contains_key(name) {
+                return Err(conflict());
+            }
+            cwd.dirs.entry(name.to_string()).or_default();
+        } else {
+            let size: usize = kind.parse().map_err(|_| invalid())?;
+            if cwd.dirs.contains_key(name)
+                || *cwd.files.entry(name.to_string()).or_insert(size) != size
+            {
+                return Err(conflict());
+            }
+        }
+        Ok(())
     }
 
-    fn parse_cli_output(&mut self, input: &str) {
-        input.lines().for_each(|l| {
-            if l.starts_with('$') {
-                let cmd = parse_command(l);
-                match cmd {
-                    Cmd::Ls => {}
-                    Cmd::Popd => {
-                        self.path.pop();
-                    }
-                    Cmd::Pushd(d) => self.path.push(d),
-                }
-            } else if l.starts_with("dir") {
-                let dir = parse_dir(l);
-                self.root_dir.push_dir(&self.path, dir);
+    /// `target` is either absolute or relative to the current directory, and may contain `..`.
+    fn cd(&mut self, line: usize, target: &str) -> Result<(), FsError> {
+        let mut path = if target.starts_with('/') {
+            Vec::new()
+        } else {
+            self.cwd.clone()
+        };
+        for name in target
+            .split('/')
+            .filter(|name| !name.is_empty() && *name != ".")
+        {
+            if name == ".." {
+                path.pop();
             } else {
-                let file = parse_file(l);
-                self.root_dir.push_file(&self.path, file);
+                path.push(name.to_string());
             }
-        });
+        }
+        if self.root.get(&path).is_none() {
+            return Err(FsError::UnknownDir {
+                line,
+                path: format!("/{}", path.join("/")),
+            });
+        }
+        self.cwd = path;
+        Ok(())
+    }
+
+    /// The directory at `path` and every directory below it, parents first,
+    /// or `None` if there is no such directory.
+    fn walk_at(&self, path: &[String]) -> Option<Vec<DirEntry<'_>>> {
+        let dir = self.root.get(path)?;
+        let mut out = Vec::new();
+        dir.walk_into(format!("/{}", path.join("/")), &mut out);
+        Some(out)
+    }
+
+    /// Every directory with its total size, parents first.
+    fn du(&self) -> Vec<DirEntry<'_>> {
+        self.walk_at(&[]).expect("the root always exists")
+    }
+
+    /// Directories whose total size matches `predicate`.
+    fn find(&self, predicate: impl Fn(usize) -> bool) -> Vec<DirEntry<'_>> {
+        self.du()
+            .into_iter()
+            .filter(|entry| predicate(entry.size))
+            .collect()
+    }
+
+    /// The `n` largest directories, largest first.
+    fn largest(&self, n: usize) -> Vec<DirEntry<'_>> {
+        let mut entries = self.du();
+        entries.sort_by_key(|entry| Reverse(entry.size));
+        entries.truncate(n);
+        entries
+    }
+
+    /// Smallest directory that frees at least `bytes` once deleted.
+    fn smallest_to_free(&self, bytes: usize) -> Option<DirEntry<'_>> {
+        self.find(|size| size >= bytes)
+            .into_iter()
+            .min_by_key(|entry| entry.size)
+    }
+
+    fn tree(&self) -> String {
+        let mut out = String::new();
+        self.root.tree_into("/", 0, &mut out);
+        out
     }
 }
 
-const TOTAL_DISK_SPACE: usize = 70_000_000;
-const NEEDED_SPACE: usize = 30_000_000;
+fn parse_command(cmd: &str) -> Option<Cmd<'_>> {
+    match cmd.split_once(' ') {
+        None if cmd == "ls" => Some(Cmd::Ls),
+        Some(("cd", target)) => Some(Cmd::Cd(target.trim())),
+        _ => None,
+    }
+}
+
+fn replay(input: &str) -> VirtualFs {
+    VirtualFs::replay(input).unwrap_or_else(|err| panic!("Invalid transcript: {err}"))
+}
 
 pub fn part_one(input: &str) -> Option<usize> {
-    let mut cli_state = CliState::new();
-    cli_state.parse_cli_output(input);
-    let subdirs = cli_state.root_dir.list_sub_dirs();
-    let sum_sub_100_000 = subdirs
-        .into_iter()
-        .filter_map(|d| {
-            let s = d.total_size();
-            if s <= 100_000 {
-                return Some(s);
-            }
-            None
-        })
-        .sum();
-    Some(sum_sub_100_000)
-}
-
-pub fn part_two(input: &str) -> Option<usize> {
-    let mut cli_state = CliState::new();
-    cli_state.parse_cli_output(input);
-
-    let missing_space = NEEDED_SPACE - (TOTAL_DISK_SPACE - cli_state.root_dir.total_size());
-    let subds = cli_state.root_dir.list_sub_dirs();
-    let mut sizes: Vec<usize> = subds.into_iter().filter_map(|d|{
-        let s = d.total_size();
-        if s <= missing_space {
-            return None;
-        }
-        Some(s)
-    }).collect();
-    sizes.sort();
-    Some(*sizes.first().unwrap())
+    let fs = replay(input);
+    Some(
+        fs.find(|size| size <= 100_000)
+            .iter()
+            .map(|entry| entry.size)
+            .sum(),
+    )
+}
+
+/// Keys of the params files.
+const PARAMS: &[&str] = &["total_disk_space", "needed_space"];
+
+pub fn part_two(input: &str, params: &Params) -> Option<usize> {
+    let total_disk_space: usize = params.get("total_disk_space", 70_000_000);
+    let needed_space: usize = params.get("needed_space", 30_000_000);
+    let fs = replay(input);
+
+    // the transcript uses more than the whole disk
+    let free_space = total_disk_space.checked_sub(fs.root.total_size())?;
+    let to_free = needed_space.saturating_sub(free_space);
+    fs.smallest_to_free(to_free).map(|entry| entry.size)
+}
+
+fn print_entries<'a>(entries: impl IntoIterator<Item = DirEntry<'a>>) {
+    for entry in entries {
+        println!(
+            "{}\t{} ({} files, {} dirs)",
+            entry.size,
+            entry.path,
+            entry.dir.files.len(),
+            entry.dir.dirs.len()
+        );
+    }
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 7);
+    let params = &advent_of_code::read_params("inputs", 7, PARAMS);
     advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    advent_of_code::solve!(2, part_two, input, params);
+    let tree = args.contains("--tree");
+    let du = args.contains("--du");
+    let at_most: Option<usize> = advent_of_code::opt_arg_in(&mut args, "--at-most", 0..);
+    let top: Option<usize> = advent_of_code::opt_arg_in(&mut args, "--top", 1..);
+    let free: Option<usize> = advent_of_code::opt_arg_in(&mut args, "--free", 0..);
+    if !(tree || du || at_most.is_some() || top.is_some() || free.is_some()) {
+        return;
+    }
+    let fs = replay(input);
+    if tree {
+        print!("{}", fs.tree());
+    }
+    if du {
+        print_entries(fs.du());
+    }
+    if let Some(at_most) = at_most {
+        print_entries(fs.find(|size| size <= at_most));
+    }
+    if let Some(n) = top {
+        print_entries(fs.largest(n));
+    }
+    if let Some(bytes) = free {
+        print_entries(fs.smallest_to_free(bytes));
+    }
 }
 
 #[cfg(test)]
@@ -206,9 +366,116 @@ mod tests {
         assert_eq!(part_one(&input), Some(95437));
     }
 
+    #[test]
+    fn test_du() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = replay(&input);
+        let du: Vec<(String, usize)> = fs.du().into_iter().map(|e| (e.path, e.size)).collect();
+        assert_eq!(
+            du,
+            [
+                ("/".to_string(), 48381165),
+                ("/a".to_string(), 94853),
+                ("/a/e".to_string(), 584),
+                ("/d".to_string(), 24933642)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 7);
+        let fs = replay(&input);
+        let paths = |entries: Vec<DirEntry>| -> Vec<String> {
+            entries.into_iter().map(|e| e.path).collect()
+        };
+        assert_eq!(paths(fs.find(|size| size <= 100_000)), ["/a", "/a/e"]);
+        assert_eq!(paths(fs.largest(2)), ["/", "/d"]);
+        assert_eq!(paths(fs.largest(10)).len(), 4);
+        let smallest = fs.smallest_to_free(90_000).unwrap();
+        assert_eq!((smallest.path.as_str(), smallest.size), ("/a", 94853));
+        assert_eq!(smallest.dir.files.len(), 3);
+        assert!(fs.smallest_to_free(50_000_000).is_none());
+        // walks from a sub-directory
+        let a = fs.walk_at(&["a".to_string()]).unwrap();
+        assert_eq!(paths(a), ["/a", "/a/e"]);
+        assert!(fs.walk_at(&["x".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_tree() {
+        let input = advent_of_code::read_file("examples", 7);
+        assert_eq!(
+            replay(&input).tree(),
+            "\
+- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - d (dir, size=24933642)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+"
+        );
+    }
+
+    #[test]
+    fn test_cd() {
+        let input = "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\ndir b\n$ cd /a/b\n$ cd ../..\n$ cd a/b";
+        let fs = VirtualFs::replay(input).unwrap();
+        assert_eq!(fs.cwd_path(), "/a/b");
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = |input| VirtualFs::replay(input).unwrap_err();
+        assert_eq!(
+            err("$ cd /\n$ cd x"),
+            FsError::UnknownDir {
+                line: 2,
+                path: "/x".to_string()
+            }
+        );
+        assert_eq!(err("$ cd /\n12 a"), FsError::OutputWithoutLs { line: 2 });
+        assert_eq!(
+            err("$ rm -rf /"),
+            FsError::UnknownCommand {
+                line: 1,
+                cmd: "rm -rf /".to_string()
+            }
+        );
+        assert_eq!(
+            err("$ ls\n12 a\n$ ls\n13 a"),
+            FsError::Conflict {
+                line: 4,
+                path: "/a".to_string()
+            }
+        );
+        assert_eq!(
+            err("$ ls\nsome junk"),
+            FsError::InvalidEntry {
+                line: 2,
+                entry: "some junk".to_string()
+            }
+        );
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 7);
-        assert_eq!(part_two(&input), Some(24933642));
+        let params = advent_of_code::read_params("examples", 7, PARAMS);
+        assert_eq!(part_two(&input, &params), Some(24933642));
+        // more used space than the disk holds
+        let small_disk = Params::from("total_disk_space = 1000")
+            .with_known(PARAMS)
+            .unwrap();
+        assert_eq!(part_two(&input, &small_disk), None);
     }
 }
@@ -1,134 +1,136 @@
 /*
--> Debug:
 🎄 Part 1 🎄
-14620 (elapsed: 83.64µs)
+14620
 🎄 Part 2 🎄
-###....##.####.###..#..#.###..####.#..#.
-#..#....#.#....#..#.#..#.#..#.#....#..#.
-###.....#.###..#..#.####.#..#.###..#..#.
-##.#....#.#....###..#..#.###..#....#..#.
-#..#.#..#.#....#.#..#..#.#.#..#....#..#.
-###...##..#....#..#.#..#.#..#.#.....##..
- (elapsed: 84.08µs)
-
--> Release:
-🎄 Part 1 🎄
-14620 (elapsed: 6.30µs)
-🎄 Part 2 🎄
-###....##.####.###..#..#.###..####.#..#.
-#..#....#.#....#..#.#..#.#..#.#....#..#.
-###.....#.###..#..#.####.#..#.###..#..#.
-##.#....#.#....###..#..#.###..#....#..#.
-#..#.#..#.#....#.#..#..#.#.#..#....#..#.
-###...##..#....#..#.#..#.#..#.#.....##..
- (elapsed: 5.44µs)
+BJFRHRFU
 */
 
+use std::fmt::Debug;
 use std::str::Lines;
 
+use advent_of_code::helpers::ocr;
 use nom::{branch::alt, bytes::complete::tag, IResult};
 
-const CYCLE_PARTICULIAR_VALUES_COUNT: usize = 6;
-const CYCLE_PARTICULIAR_VALUES: [usize; CYCLE_PARTICULIAR_VALUES_COUNT] =
-    [20, 60, 100, 140, 180, 220];
+const SIGNAL_SAMPLE_CYCLES: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+#[derive(Debug, Clone, Copy)]
+struct Registers {
+    x: isize,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers { x: 1 }
+    }
+}
+
+/// An instruction takes `cycles()` cycles, then applies its effect on the registers.
+trait Instr: Debug {
+    fn cycles(&self) -> usize;
+    fn execute(&self, regs: &mut Registers);
+}
+
+#[derive(Debug)]
+struct Noop;
+
+impl Instr for Noop {
+    fn cycles(&self) -> usize {
+        1
+    }
+    fn execute(&self, _regs: &mut Registers) {}
+}
 
 #[derive(Debug)]
-enum Instr {
-    Noop,
-    Addx(i8),
+struct Addx(i8);
+
+impl Instr for Addx {
+    fn cycles(&self) -> usize {
+        2
+    }
+    fn execute(&self, regs: &mut Registers) {
+        regs.x += self.0 as isize;
+    }
 }
 
 struct InstrParser {}
 impl InstrParser {
-    fn parse_noop(input: &str) -> IResult<&str, Instr> {
+    fn parse_noop(input: &str) -> IResult<&str, Box<dyn Instr>> {
         let (input, _) = tag("noop")(input)?;
-        Ok((input, Instr::Noop))
+        Ok((input, Box::new(Noop)))
     }
 
-    fn parse_addx(input: &str) -> IResult<&str, Instr> {
+    fn parse_addx(input: &str) -> IResult<&str, Box<dyn Instr>> {
         let (input, _) = tag("addx ")(input)?;
         Ok((
             "",
-            Instr::Addx(input.parse::<i8>().expect("Failed parsing i8.")),
+            Box::new(Addx(input.parse::<i8>().expect("Failed parsing i8."))),
         ))
     }
 
-    fn parse_instr(input: &str) -> IResult<&str, Instr> {
+    fn parse_instr(input: &str) -> IResult<&str, Box<dyn Instr>> {
         alt((Self::parse_noop, Self::parse_addx))(input)
     }
 
-    fn parse(input: Lines) -> Vec<Instr> {
+    fn parse(input: Lines) -> Vec<Box<dyn Instr>> {
         input
             .map(|l| Self::parse_instr(l).expect("Failed parsing instr").1)
             .collect()
     }
 }
 
+/// Notified during every cycle, before the running instruction has completed.
+trait Observer {
+    /// `cycle` starts at 1.
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers, instr: &dyn Instr);
+}
+
 struct VCpu {
-    x_reg: isize,
+    regs: Registers,
     cycle: usize,
-    next_cycle_particuliar_value_index: Option<usize>,
-    interesting_signal_strenghts: [Option<isize>; CYCLE_PARTICULIAR_VALUES_COUNT],
-    crt: Option<Crt>,
 }
 
 impl VCpu {
     fn new() -> Self {
         VCpu {
-            x_reg: 1,
+            regs: Registers::default(),
             cycle: 0,
-            next_cycle_particuliar_value_index: Some(0),
-            interesting_signal_strenghts: [None; CYCLE_PARTICULIAR_VALUES_COUNT],
-            crt: None,
         }
     }
-    fn set_crt(&mut self) {
-        self.crt = Some(Crt::new());
-    }
-    fn get_signal_strength(&self) -> isize {
-        self.x_reg * self.cycle as isize
-    }
-    fn get_total_signal_strength(&self) -> isize {
-        self.interesting_signal_strenghts
-            .iter()
-            .filter_map(|&e| e)
-            .sum()
-    }
-    fn increase_cycle(&mut self) {
-        if let Some(crt) = &mut self.crt {
-            crt.draw_pixel(self.x_reg, self.cycle);
-        }
-        self.cycle += 1;
-        if let Some(index) = self.next_cycle_particuliar_value_index {
-            if self.cycle == CYCLE_PARTICULIAR_VALUES[index] {
-                self.interesting_signal_strenghts[index] = Some(self.get_signal_strength());
-                self.next_cycle_particuliar_value_index =
-                    if index + 1 < CYCLE_PARTICULIAR_VALUES_COUNT {
-                        Some(index + 1)
-                    } else {
-                        None
-                    }
+    fn run_instr(&mut self, instr: &dyn Instr, observers: &mut [&mut dyn Observer]) {
+        for _ in 0..instr.cycles() {
+            self.cycle += 1;
+            for observer in observers.iter_mut() {
+                observer.on_cycle(self.cycle, &self.regs, instr);
             }
         }
+        instr.execute(&mut self.regs);
     }
-    fn run_instr(&mut self, instr: &Instr) {
-        match instr {
-            Instr::Noop => {
-                self.increase_cycle();
-            }
-            Instr::Addx(d) => {
-                self.increase_cycle();
-                self.increase_cycle();
-                self.x_reg += *d as isize;
-            }
+    fn run(&mut self, instrs: &[Box<dyn Instr>], observers: &mut [&mut dyn Observer]) {
+        for instr in instrs {
+            self.run_instr(instr.as_ref(), observers);
         }
     }
-    fn compute_display(&mut self, instrs: &Vec<Instr>) {
-        if self.crt.is_none() {
-            panic!("Called compute_display without CRT!!");
+}
+
+/// Sums the signal strength (cycle times `x`) during the given cycles.
+struct SignalSampler {
+    cycles: Vec<usize>,
+    total: isize,
+}
+
+impl SignalSampler {
+    fn new(cycles: &[usize]) -> Self {
+        SignalSampler {
+            cycles: cycles.to_vec(),
+            total: 0,
         }
-        for instr in instrs {
-            self.run_instr(instr);
+    }
+}
+
+impl Observer for SignalSampler {
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers, _instr: &dyn Instr) {
+        if self.cycles.contains(&cycle) {
+            self.total += regs.x * cycle as isize;
         }
     }
 }
@@ -146,8 +148,8 @@ impl Crt {
 
     fn draw_pixel(&mut self, x_reg: isize, cycle: usize) {
         let npixel_index = cycle % 40;
-        let x: usize = x_reg.clamp(0, 39) as usize;
-        if npixel_index <= x + 1 && npixel_index >= x.saturating_sub(1) {
+        // the sprite is 3 pixels wide, centered on x
+        if (npixel_index as isize - x_reg).abs() <= 1 {
             self.screen.push('#');
         } else {
             self.screen.push('.')
@@ -158,25 +160,50 @@ impl Crt {
     }
 }
 
+impl Observer for Crt {
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers, _instr: &dyn Instr) {
+        self.draw_pixel(regs.x, cycle - 1);
+    }
+}
+
+/// Prints the registers during every cycle.
+struct Tracer;
+
+impl Observer for Tracer {
+    fn on_cycle(&mut self, cycle: usize, regs: &Registers, instr: &dyn Instr) {
+        println!("cycle {cycle:>3} | x = {:>3} | {instr:?}", regs.x);
+    }
+}
+
 pub fn part_one(input: &str) -> Option<isize> {
     let instrs = InstrParser::parse(input.lines());
-    let mut vcpu = VCpu::new();
-    instrs.iter().for_each(|instr| vcpu.run_instr(instr));
-    Some(vcpu.get_total_signal_strength())
+    let mut sampler = SignalSampler::new(&SIGNAL_SAMPLE_CYCLES);
+    VCpu::new().run(&instrs, &mut [&mut sampler]);
+    Some(sampler.total)
 }
 
-pub fn part_two(input: &str) -> Option<String> {
+fn draw_screen(input: &str) -> String {
     let instrs = InstrParser::parse(input.lines());
-    let mut vcpu = VCpu::new();
-    vcpu.set_crt();
-    vcpu.compute_display(&instrs);
-    Some(vcpu.crt.unwrap().screen)
+    let mut crt = Crt::new();
+    VCpu::new().run(&instrs, &mut [&mut crt]);
+    crt.screen
+}
+
+/// The letters drawn on the screen, or the screen itself if they are not recognized.
+pub fn part_two(input: &str) -> Option<String> {
+    let screen = draw_screen(input);
+    Some(ocr::recognize(&screen).unwrap_or(screen))
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 10);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    if args.contains("--trace") {
+        let instrs = InstrParser::parse(input.lines());
+        VCpu::new().run(&instrs, &mut [&mut Tracer]);
+    }
 }
 
 #[cfg(test)]
@@ -190,21 +217,81 @@ mod tests {
     }
 
     #[test]
-    fn test_part_two() {
+    fn test_observers() {
+        /// Records `x` during every cycle.
+        struct Recorder(Vec<isize>);
+        impl Observer for Recorder {
+            fn on_cycle(&mut self, _cycle: usize, regs: &Registers, _instr: &dyn Instr) {
+                self.0.push(regs.x);
+            }
+        }
+        let instrs = InstrParser::parse("noop\naddx 3\naddx -5\nnoop".lines());
+        let (mut recorder, mut sampler) = (Recorder(Vec::new()), SignalSampler::new(&[3, 5]));
+        let mut vcpu = VCpu::new();
+        vcpu.run(&instrs, &mut [&mut recorder, &mut sampler]);
+        assert_eq!(recorder.0, [1, 1, 1, 4, 4, -1]);
+        assert_eq!(sampler.total, 3 + 4 * 5);
+        assert_eq!((vcpu.cycle, vcpu.regs.x), (6, -1));
+    }
+
+    #[test]
+    fn test_draw_screen() {
         let input = advent_of_code::read_file("examples", 10);
         assert_eq!(
-            part_two(&input),
-            Some(
-                [
-                    "##..##..##..##..##..##..##..##..##..##..",
-                    "###...###...###...###...###...###...###.",
-                    "####....####....####....####....####....",
-                    "#####.....#####.....#####.....#####.....",
-                    "######......######......######......####",
-                    "#######.......#######.......#######.....\n",
-                ]
-                .join("\n")
-            )
+            draw_screen(&input),
+            ([
+                "##..##..##..##..##..##..##..##..##..##..",
+                "###...###...###...###...###...###...###.",
+                "####....####....####....####....####....",
+                "#####.....#####.....#####.....#####.....",
+                "######......######......######......####",
+                "#######.......#######.......#######.....\n",
+            ]
+            .join("\n"))
         );
     }
+
+    #[test]
+    fn test_part_two() {
+        // the example draws stripes, not letters
+        let input = advent_of_code::read_file("examples", 10);
+        assert_eq!(part_two(&input), Some(draw_screen(&input)));
+    }
+
+    /// A program drawing `screen`: each `addx` lasts two pixels of a row,
+    /// during which the sprite is placed to light exactly the wanted ones.
+    fn program_drawing(screen: &str) -> String {
+        let pixels: Vec<bool> = screen
+            .lines()
+            .flat_map(|row| row.bytes().map(|b| b == b'#'))
+            .collect();
+        let sprites: Vec<i32> = pixels
+            .chunks(2)
+            .enumerate()
+            .map(|(pair, lit)| {
+                let p = (pair * 2 % 40) as i32;
+                match lit {
+                    [true, true] => p + 1,
+                    [true, false] => p - 1,
+                    [false, true] => p + 2,
+                    _ => -3,
+                }
+            })
+            .collect();
+        assert_eq!(sprites[0], 1, "the sprite starts at 1");
+        sprites
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .chain([0])
+            .map(|v| format!("addx {v}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn test_part_two_letters() {
+        let screen = ocr::draw("BEEFRLPZ").unwrap();
+        let input = program_drawing(&screen);
+        assert_eq!(draw_screen(&input), screen);
+        assert_eq!(part_two(&input), Some("BEEFRLPZ".to_string()));
+    }
 }
@@ -11,57 +11,101 @@
 🎄 Part 2 🎄
 25712998901 (elapsed: 9.15ms)
 */
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    fs::File,
+    io::{self, BufWriter, Write},
+    process,
+    str::FromStr,
+};
 
+use advent_of_code::{
+    helpers::{
+        parse::{records, RecordError},
+        TopN,
+    },
+    Params,
+};
 use nom::{
     self,
     branch::alt,
     bytes::complete::tag,
-    character::complete::{alphanumeric1, digit1, line_ending},
+    character::complete::{alphanumeric1, digit1, line_ending, space0},
     combinator::{map, opt},
     multi::{many0, many1},
     sequence::{delimited, pair, preceded, tuple},
     IResult,
 };
 
-#[derive(Debug)]
-enum Var {
-    Old,
-    Val(usize),
+/// Worry levels, signed so that `-` may go below zero. Without a modulus they grow
+/// unbounded: going past `i128` (about 1.7e38) stops the game with `KeepAwayError::Overflow`.
+type Worry = i128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
 }
 
-impl Var {
-    fn val(&self) -> Option<usize> {
+impl BinOp {
+    /// `None` on overflow or division by zero.
+    fn apply(&self, lhs: Worry, rhs: Worry) -> Option<Worry> {
         match self {
-            Var::Old => None,
-            Var::Val(u) => Some(*u),
+            BinOp::Add => lhs.checked_add(rhs),
+            BinOp::Sub => lhs.checked_sub(rhs),
+            BinOp::Mul => lhs.checked_mul(rhs),
+            BinOp::Div => lhs.checked_div(rhs),
+            BinOp::Rem => lhs.checked_rem(rhs),
         }
     }
+
+    /// Whether `a op b` modulo `m` only depends on `a` and `b` modulo `m`.
+    fn is_modular(&self) -> bool {
+        matches!(self, BinOp::Add | BinOp::Sub | BinOp::Mul)
+    }
 }
 
-#[derive(Debug)]
+/// Right-hand side of a monkey operation, computed from the `old` worry level.
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Operation {
-    Mul(Var, Var),
-    Sum(Var, Var),
+    Old,
+    Val(Worry),
+    BinOp(Box<Operation>, BinOp, Box<Operation>),
 }
 
 impl Operation {
-    fn cpt(&self, old: usize) -> usize {
+    /// `None` on overflow or division by zero.
+    fn cpt(&self, old: Worry) -> Option<Worry> {
         match self {
-            Operation::Mul(v1, v2) => v1.val().unwrap_or(old) * v2.val().unwrap_or(old),
-            Operation::Sum(v1, v2) => v1.val().unwrap_or(old) + v2.val().unwrap_or(old),
+            Operation::Old => Some(old),
+            Operation::Val(v) => Some(*v),
+            Operation::BinOp(lhs, op, rhs) => op.apply(lhs.cpt(old)?, rhs.cpt(old)?),
+        }
+    }
+
+    /// Whether worry levels may be reduced modulo the test dividers before this operation.
+    fn is_modular(&self) -> bool {
+        match self {
+            Operation::Old | Operation::Val(_) => true,
+            Operation::BinOp(lhs, op, rhs) => {
+                op.is_modular() && lhs.is_modular() && rhs.is_modular()
+            }
         }
     }
 }
 
 #[derive(Debug)]
 struct Item {
-    worry_level: usize,
+    worry_level: Worry,
 }
 
 #[derive(Debug)]
 struct Test {
-    divider: usize,
+    divider: Worry,
     true_monkey: usize,
     false_monkey: usize,
 }
@@ -76,15 +120,20 @@ struct Monkey {
 }
 
 impl Monkey {
-    fn inspect_item(&mut self, item: &mut Item, relief: bool, relief_modulus: usize) {
+    /// `None` if the new worry level overflows.
+    fn inspect_item(
+        &mut self,
+        item: &mut Item,
+        relief_divisor: Worry,
+        modulus: Option<Worry>,
+    ) -> Option<()> {
         // Monkey inspects item
-        item.worry_level = self.operation.cpt(item.worry_level);
-        if relief {
-            item.worry_level = (item.worry_level as f64 / 3.).floor() as usize;
-        } else {
-            item.worry_level %= relief_modulus
+        item.worry_level = self.operation.cpt(item.worry_level)? / relief_divisor;
+        if let Some(modulus) = modulus {
+            item.worry_level %= modulus;
         }
         self.amount_inspect += 1;
+        Some(())
     }
 
     fn test_item(&mut self, item: &Item) -> usize {
@@ -94,16 +143,25 @@ impl Monkey {
         self.test.false_monkey
     }
 
-    fn step_play_turn(&mut self, relief: bool, relief_modulus: usize) -> Option<(usize, Item)> {
+    fn step_play_turn(
+        &mut self,
+        relief_divisor: Worry,
+        modulus: Option<Worry>,
+        round: usize,
+    ) -> Result<Option<(usize, Item)>, KeepAwayError> {
         if self.items.is_empty() {
-            return None;
+            return Ok(None);
         }
         let mut item = self.items.pop_front().unwrap();
 
-        self.inspect_item(&mut item, relief, relief_modulus);
+        self.inspect_item(&mut item, relief_divisor, modulus)
+            .ok_or(KeepAwayError::Overflow {
+                round,
+                monkey: self.id,
+            })?;
         let monkey_id = self.test_item(&item);
 
-        Some((monkey_id, item))
+        Ok(Some((monkey_id, item)))
     }
 }
 
@@ -117,7 +175,7 @@ fn parse_item(input: &str) -> IResult<&str, Item> {
     preceded(
         alt((tag(" "), tag(", "))),
         map(digit1, |d: &str| Item {
-            worry_level: d.parse::<usize>().expect("Failed parsing Item digit."),
+            worry_level: d.parse::<Worry>().expect("Failed parsing Item digit."),
         }),
     )(input)
 }
@@ -127,29 +185,52 @@ fn parse_items(input: &str) -> IResult<&str, Vec<Item>> {
 }
 
 /* Parse Operation */
-fn parse_var(input: &str) -> IResult<&str, Var> {
+fn parse_operand(input: &str) -> IResult<&str, Operation> {
     alt((
-        map(tag("old"), |_| Var::Old),
-        map(digit1, |d: &str| Var::Val(d.parse::<usize>().unwrap())),
+        map(tag("old"), |_| Operation::Old),
+        map(digit1, |d: &str| {
+            Operation::Val(d.parse::<Worry>().unwrap())
+        }),
+        delimited(tag("("), parse_expr, tag(")")),
     ))(input)
 }
 
+/// Left-associative chain of `operand` separated by any of `ops`.
+fn parse_chain<'a>(
+    input: &'a str,
+    operand: fn(&str) -> IResult<&str, Operation>,
+    ops: &[(&'static str, BinOp)],
+) -> IResult<&'a str, Operation> {
+    let (mut input, mut expr) = operand(input)?;
+    'chain: loop {
+        for &(symbol, op) in ops {
+            let parsed: IResult<&str, Operation> =
+                preceded(delimited(space0, tag(symbol), space0), operand)(input);
+            if let Ok((rest, rhs)) = parsed {
+                expr = Operation::BinOp(Box::new(expr), op, Box::new(rhs));
+                input = rest;
+                continue 'chain;
+            }
+        }
+        return Ok((input, expr));
+    }
+}
+
+fn parse_term(input: &str) -> IResult<&str, Operation> {
+    parse_chain(
+        input,
+        parse_operand,
+        &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+    )
+}
+
+/// `+`, `-`, `*`, `/` and `%` over `old` and constants, with the usual precedence and parentheses.
+fn parse_expr(input: &str) -> IResult<&str, Operation> {
+    parse_chain(input, parse_term, &[("+", BinOp::Add), ("-", BinOp::Sub)])
+}
+
 fn parse_operation(input: &str) -> IResult<&str, Operation> {
-    preceded(
-        tag("Operation: new = "),
-        map(
-            tuple((
-                parse_var,
-                delimited(tag(" "), alt((tag("+"), tag("*"))), tag(" ")),
-                parse_var,
-            )),
-            |(var1, operator, var2)| match operator {
-                "*" => Operation::Mul(var1, var2),
-                "+" => Operation::Sum(var1, var2),
-                _ => unreachable!(),
-            },
-        ),
-    )(input)
+    preceded(tag("Operation: new = "), parse_expr)(input)
 }
 
 /* Parse Test */
@@ -174,7 +255,7 @@ fn parse_test(input: &str) -> IResult<&str, Test> {
     let (to_parse, (divider, (bool1, id1), (bool2, id2))) = tuple((
         delimited(
             tag("Test: divisible by "),
-            map(digit1, |d: &str| d.parse::<usize>().unwrap()),
+            map(digit1, |d: &str| d.parse::<Worry>().unwrap()),
             line_ending,
         ),
         delimited(tabbing, parse_branch, line_ending),
@@ -229,98 +310,270 @@ fn parse_monkey(input: &str) -> IResult<&str, Monkey> {
     )(input)
 }
 
-fn parse_monkeys(input: &str) -> IResult<&str, Vec<Monkey>> {
-    many1(preceded(opt(pair(tabbing, line_ending)), parse_monkey))(input)
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, RecordError> {
+    records(input)
+        .map(|record| record.parse(parse_monkey))
+        .collect()
+}
+
+/// How worry levels are kept manageable after each inspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModulusStrategy {
+    /// Worry levels grow unbounded, see `Worry`.
+    None,
+    /// Modulo the product of every monkey test divider.
+    Product,
+    /// Modulo the least common multiple of every monkey test divider.
+    Lcm,
+}
+
+impl ModulusStrategy {
+    /// Reducing worry levels keeps test results only when there is no relief and every
+    /// operation uses `+`, `-` and `*`: otherwise, only `None` is allowed.
+    fn modulus(
+        &self,
+        monkeys: &[Monkey],
+        relief_divisor: Worry,
+    ) -> Result<Option<Worry>, KeepAwayError> {
+        if *self == ModulusStrategy::None {
+            return Ok(None);
+        }
+        if relief_divisor != 1 {
+            return Err(KeepAwayError::NotModular {
+                modulus: *self,
+                monkey: None,
+            });
+        }
+        if let Some(monkey) = monkeys.iter().find(|m| !m.operation.is_modular()) {
+            return Err(KeepAwayError::NotModular {
+                modulus: *self,
+                monkey: Some(monkey.id),
+            });
+        }
+        let dividers = monkeys.iter().map(|m| m.test.divider);
+        Ok(Some(match self {
+            ModulusStrategy::None => unreachable!(),
+            ModulusStrategy::Product => dividers.product(),
+            ModulusStrategy::Lcm => dividers.fold(1, |acc, d| acc / gcd(acc, d) * d),
+        }))
+    }
+}
+
+impl FromStr for ModulusStrategy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ModulusStrategy::None),
+            "product" => Ok(ModulusStrategy::Product),
+            "lcm" => Ok(ModulusStrategy::Lcm),
+            _ => Err(format!("Unknown modulus strategy: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum KeepAwayError {
+    /// A modulus strategy with relief, or with an operation using `/` or `%` (in `monkey`):
+    /// reducing worry levels would change test results.
+    NotModular {
+        modulus: ModulusStrategy,
+        monkey: Option<usize>,
+    },
+    /// An exact worry level went past `Worry`, or an operation divided by zero.
+    Overflow { round: usize, monkey: usize },
+}
+
+impl Display for KeepAwayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeepAwayError::NotModular {
+                modulus,
+                monkey: None,
+            } => write!(f, "modulus {modulus:?} needs a relief of 1"),
+            KeepAwayError::NotModular {
+                modulus,
+                monkey: Some(monkey),
+            } => write!(
+                f,
+                "modulus {modulus:?} needs +, - and * operations only, monkey {monkey} uses / or %"
+            ),
+            KeepAwayError::Overflow { round, monkey } => write!(
+                f,
+                "round {round}: worry level overflow or division by zero at monkey {monkey}"
+            ),
+        }
+    }
+}
+
+fn gcd(a: Worry, b: Worry) -> Worry {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Keys of the params files.
+const PARAMS: &[&str] = &[
+    "part_one.rounds",
+    "part_one.relief",
+    "part_one.modulus",
+    "part_two.rounds",
+    "part_two.relief",
+    "part_two.modulus",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct KeepAwayRules {
+    rounds: usize,
+    /// Worry levels are divided by this after each inspection, 1 means no relief.
+    relief_divisor: Worry,
+    modulus: ModulusStrategy,
+}
+
+impl KeepAwayRules {
+    const PART_ONE: KeepAwayRules = KeepAwayRules {
+        rounds: 20,
+        relief_divisor: 3,
+        modulus: ModulusStrategy::None,
+    };
+    const PART_TWO: KeepAwayRules = KeepAwayRules {
+        rounds: 10_000,
+        relief_divisor: 1,
+        modulus: ModulusStrategy::Lcm,
+    };
+
+    /// These rules, overridden by the `<part>.rounds`, `<part>.relief` and `<part>.modulus` params,
+    /// see `PARAMS`. Panics on a zero relief divisor.
+    fn with_params(self, part: &str, params: &Params) -> Self {
+        let relief_divisor = params.get(&format!("{part}.relief"), self.relief_divisor);
+        assert_ne!(relief_divisor, 0, "param {part}.relief must be at least 1");
+        KeepAwayRules {
+            rounds: params.get(&format!("{part}.rounds"), self.rounds),
+            relief_divisor,
+            modulus: params.get(&format!("{part}.modulus"), self.modulus),
+        }
+    }
 }
 
 struct KeepAway {
     monkeys: Vec<Monkey>,
-    relief: bool,
-    fake_relief_divider: usize,
+    rules: KeepAwayRules,
+    modulus: Option<Worry>,
+    /// Items inspected by each monkey so far, after each round.
+    inspections: Vec<Vec<usize>>,
 }
 
 impl KeepAway {
-    fn new(monkeys: Vec<Monkey>, relief: bool) -> Self {
-        let fake_relief_divider = if !relief {
-            monkeys.iter().fold(1, |acc, m| acc * m.test.divider)
-        } else {
-            0
-        };
-
-        KeepAway {
+    fn new(monkeys: Vec<Monkey>, rules: KeepAwayRules) -> Result<Self, KeepAwayError> {
+        let modulus = rules.modulus.modulus(&monkeys, rules.relief_divisor)?;
+        Ok(KeepAway {
             monkeys,
-            relief,
-            fake_relief_divider,
-        }
+            rules,
+            modulus,
+            inspections: Vec::new(),
+        })
     }
 
-    fn play_turn(&mut self, monkey_id: usize) {
+    fn play_turn(&mut self, monkey_id: usize, round: usize) -> Result<(), KeepAwayError> {
         assert_eq!(monkey_id, self.monkeys[monkey_id].id);
         while let Some((target_monkey_id, item)) = self
             .monkeys
             .get_mut(monkey_id)
             .unwrap()
-            .step_play_turn(self.relief, self.fake_relief_divider)
+            .step_play_turn(self.rules.relief_divisor, self.modulus, round)?
         {
-            // println!(
-            //     "\tItem with worry level {} is thrown to monkey {target_monkey_id}",
-            //     item.worry_level
-            // );
             self.monkeys[target_monkey_id].items.push_back(item)
         }
+        Ok(())
     }
 
-    fn play_round(&mut self) {
+    fn play_round(&mut self, round: usize) -> Result<(), KeepAwayError> {
         for id in 0..self.monkeys.len() {
-            // println!("Monkey {id}:");
-            self.play_turn(id);
+            self.play_turn(id, round)?;
+        }
+        self.inspections
+            .push(self.monkeys.iter().map(|m| m.amount_inspect).collect());
+        Ok(())
+    }
+
+    fn play(&mut self) -> Result<(), KeepAwayError> {
+        for round in 1..=self.rules.rounds {
+            self.play_round(round)?;
         }
+        Ok(())
     }
 
     fn monkey_business(&self) -> usize {
-        let mut inspect_amounts: Vec<usize> =
-            self.monkeys.iter().map(|m| m.amount_inspect).collect();
-        // for (index, ia) in inspect_amounts.iter().enumerate() {
-        // println!("Monkey {index} => inspected {ia} items !");
-        // }
-        inspect_amounts.sort();
-        assert!(inspect_amounts.len() >= 2);
-        inspect_amounts.last().unwrap() * inspect_amounts[inspect_amounts.len() - 2]
+        let mut most_active = TopN::<_, 2>::by_key(|m: &&Monkey| m.amount_inspect);
+        most_active.extend(&self.monkeys);
+        assert_eq!(most_active.len(), 2);
+        most_active.iter().map(|m| m.amount_inspect).product()
+    }
+
+    /// Inspections per monkey after each round, as CSV.
+    fn write_stats(&self, out: &mut impl Write) -> io::Result<()> {
+        let header: Vec<String> = (0..self.monkeys.len())
+            .map(|id| format!("monkey_{id}"))
+            .collect();
+        writeln!(out, "round,{}", header.join(","))?;
+        for (round, counts) in self.inspections.iter().enumerate() {
+            let counts: Vec<String> = counts.iter().map(usize::to_string).collect();
+            writeln!(out, "{},{}", round + 1, counts.join(","))?;
+        }
+        Ok(())
     }
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let (rest, monkeys) = parse_monkeys(input).expect("Failed parsing Monkeys.");
-    assert_eq!(rest.trim(), "");
-    let mut keep_away = KeepAway::new(monkeys, true);
+fn play(input: &str, rules: KeepAwayRules) -> Result<KeepAway, KeepAwayError> {
+    let monkeys =
+        parse_monkeys(input).unwrap_or_else(|err| panic!("Failed parsing Monkeys: {err}"));
+    let mut keep_away = KeepAway::new(monkeys, rules)?;
+    keep_away.play()?;
+    Ok(keep_away)
+}
 
-    for _i in 0..20 {
-        // println!("round {_i}:");
-        keep_away.play_round();
-        // println!("====");
-    }
-    Some(keep_away.monkey_business())
+fn monkey_business(input: &str, rules: KeepAwayRules) -> usize {
+    play(input, rules)
+        .unwrap_or_else(|err| panic!("Failed playing keep away: {err}"))
+        .monkey_business()
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let (rest, monkeys) = parse_monkeys(input).expect("Failed parsing Monkeys.");
-    assert_eq!(rest.trim(), "");
-    let mut keep_away = KeepAway::new(monkeys, false);
+pub fn part_one(input: &str, params: &Params) -> Option<usize> {
+    let rules = KeepAwayRules::PART_ONE.with_params("part_one", params);
+    Some(monkey_business(input, rules))
+}
 
-    for _i in 0..10_000 {
-        // if _i % 100 == 0 {
-        //     println!("round {_i}:");
-        // }
-        keep_away.play_round();
-        // println!("====");
-    }
-    Some(keep_away.monkey_business())
+pub fn part_two(input: &str, params: &Params) -> Option<usize> {
+    let rules = KeepAwayRules::PART_TWO.with_params("part_two", params);
+    Some(monkey_business(input, rules))
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 11);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let params = &advent_of_code::read_params("inputs", 11, PARAMS);
+    advent_of_code::solve!(1, part_one, input, params);
+    advent_of_code::solve!(2, part_two, input, params);
+    let stats_path: Option<String> = args.opt_value_from_str("--stats").unwrap();
+    if let Some(path) = stats_path {
+        let default = KeepAwayRules::PART_TWO;
+        let rules = KeepAwayRules {
+            rounds: advent_of_code::opt_arg_in(&mut args, "--rounds", 0..)
+                .unwrap_or(default.rounds),
+            relief_divisor: advent_of_code::opt_arg_in(&mut args, "--relief", 1..)
+                .unwrap_or(default.relief_divisor),
+            modulus: advent_of_code::opt_arg(&mut args, "--modulus").unwrap_or(default.modulus),
+        };
+        let keep_away = play(input, rules).unwrap_or_else(|err| {
+            eprintln!("Could not play keep away: {err}");
+            process::exit(1);
+        });
+        let mut out = BufWriter::new(File::create(path).expect("Could not create stats file"));
+        keep_away
+            .write_stats(&mut out)
+            .expect("Could not write stats");
+    }
 }
 
 #[cfg(test)]
@@ -330,20 +583,178 @@ mod tests {
     #[test]
     fn test_parse_monkeys() {
         let input = advent_of_code::read_file("examples", 11);
-        let (rest, monkeys) = parse_monkeys(input.as_str()).expect("Failed to parse monkeys");
-        println!("rest: {rest}");
+        let monkeys = parse_monkeys(input.as_str()).expect("Failed to parse monkeys");
         assert_eq!(monkeys.len(), 4);
     }
 
+    #[test]
+    fn test_parse_operation() {
+        let expr = |input| parse_expr(input).unwrap().1;
+        assert_eq!(expr("old * 19").cpt(2), Some(38));
+        assert_eq!(expr("old * old").cpt(7), Some(49));
+        assert_eq!(expr("old + 6 * 2").cpt(1), Some(13));
+        assert_eq!(expr("(old + 6) * 2").cpt(1), Some(14));
+        assert_eq!(expr("old - 10 / 3 % 2").cpt(5), Some(4));
+        assert_eq!(expr("old - 1 - 1").cpt(5), Some(3));
+        assert_eq!(expr("old - 7").cpt(1), Some(-6));
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = advent_of_code::read_file("examples", 11);
+        let product = KeepAwayRules {
+            modulus: ModulusStrategy::Product,
+            ..KeepAwayRules::PART_TWO
+        };
+        assert_eq!(play(&input, product).unwrap().monkey_business(), 2713310158);
+        let short = KeepAwayRules {
+            rounds: 20,
+            ..KeepAwayRules::PART_TWO
+        };
+        assert_eq!(play(&input, short).unwrap().monkey_business(), 99 * 103);
+    }
+
+    #[test]
+    fn test_non_modular_operations() {
+        let input = "\
+Monkey 0:
+  Starting items: 1, 9, 30
+  Operation: new = old - 7
+  Test: divisible by 2
+    If true: throw to monkey 1
+    If false: throw to monkey 2
+
+Monkey 1:
+  Starting items: 4
+  Operation: new = old / 2 + 4
+  Test: divisible by 3
+    If true: throw to monkey 0
+    If false: throw to monkey 2
+
+Monkey 2:
+  Starting items: 2
+  Operation: new = old + 3
+  Test: divisible by 5
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+        let monkeys = parse_monkeys(input).unwrap();
+        let not_modular = |modulus| KeepAwayError::NotModular {
+            modulus,
+            monkey: Some(1),
+        };
+        assert_eq!(
+            ModulusStrategy::Lcm.modulus(&monkeys, 1),
+            Err(not_modular(ModulusStrategy::Lcm))
+        );
+        assert_eq!(
+            ModulusStrategy::Product.modulus(&monkeys, 1),
+            Err(not_modular(ModulusStrategy::Product))
+        );
+        assert_eq!(ModulusStrategy::None.modulus(&monkeys, 1), Ok(None));
+        let exact = KeepAwayRules {
+            modulus: ModulusStrategy::None,
+            ..KeepAwayRules::PART_TWO
+        };
+        assert!(play(input, exact).is_ok());
+        let example = parse_monkeys(&advent_of_code::read_file("examples", 11)).unwrap();
+        assert_eq!(ModulusStrategy::Lcm.modulus(&example, 1), Ok(Some(96577)));
+        assert_eq!(
+            ModulusStrategy::Lcm.modulus(&example, 3),
+            Err(KeepAwayError::NotModular {
+                modulus: ModulusStrategy::Lcm,
+                monkey: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_unbounded_rules() {
+        let input = advent_of_code::read_file("examples", 11);
+        let exact = KeepAwayRules {
+            modulus: ModulusStrategy::None,
+            ..KeepAwayRules::PART_TWO
+        };
+        assert!(matches!(
+            play(&input, exact),
+            Err(KeepAwayError::Overflow { .. })
+        ));
+        let halved = input.replace("old * old", "old * old / 2");
+        assert!(matches!(
+            play(&halved, KeepAwayRules::PART_TWO),
+            Err(KeepAwayError::NotModular {
+                modulus: ModulusStrategy::Lcm,
+                monkey: Some(2)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_subtraction_is_modular() {
+        let input = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old - 5
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 54, 65
+  Operation: new = old * old
+  Test: divisible by 19
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+        let monkeys = parse_monkeys(input).unwrap();
+        assert_eq!(ModulusStrategy::Lcm.modulus(&monkeys, 1), Ok(Some(23 * 19)));
+        assert_eq!(part_two(input, &Params::default()), Some(39_998 * 40_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "param part_one.relief must be at least 1")]
+    fn test_zero_relief() {
+        let input = advent_of_code::read_file("examples", 11);
+        let params = Params::from("part_one.relief = 0")
+            .with_known(PARAMS)
+            .unwrap();
+        part_one(&input, &params);
+    }
+
+    #[test]
+    fn test_rules_params() {
+        let input = advent_of_code::read_file("examples", 11);
+        let params = Params::from("part_two.rounds = 20\npart_two.modulus = product")
+            .with_known(PARAMS)
+            .unwrap();
+        assert_eq!(part_two(&input, &params), Some(99 * 103));
+        assert_eq!(part_one(&input, &params), Some(10605));
+    }
+
+    #[test]
+    fn test_write_stats() {
+        let input = advent_of_code::read_file("examples", 11);
+        let keep_away = play(&input, KeepAwayRules::PART_ONE).unwrap();
+        let mut csv = Vec::new();
+        keep_away.write_stats(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 21);
+        assert_eq!(lines[0], "round,monkey_0,monkey_1,monkey_2,monkey_3");
+        assert_eq!(lines[20], "20,101,95,7,105");
+    }
+
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_one(&input), Some(10605));
+        let params = advent_of_code::read_params("examples", 11, PARAMS);
+        assert_eq!(part_one(&input, &params), Some(10605));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 11);
-        assert_eq!(part_two(&input), Some(2713310158));
+        let params = advent_of_code::read_params("examples", 11, PARAMS);
+        assert_eq!(part_two(&input, &params), Some(2713310158));
     }
 }
@@ -1,13 +1,12 @@
-#![feature(iter_array_chunks)]
-
+use advent_of_code::helpers::parse::{records, RecordError};
 use nom::{
     self,
     branch::alt,
     bytes::complete::tag,
-    character::complete::digit1,
+    character::complete::{digit1, line_ending},
     combinator::{map, opt},
     multi::many0,
-    sequence::{delimited, preceded},
+    sequence::{delimited, preceded, separated_pair},
     IResult,
 };
 use std::cmp::Ordering;
@@ -83,18 +82,18 @@ impl From<&str> for Tree {
     }
 }
 
+/// Pairs of packets, each record must be exactly two packets.
+fn parse_pairs(input: &str) -> Result<Vec<(Tree, Tree)>, RecordError> {
+    records(input)
+        .map(|record| record.parse(|r| separated_pair(Tree::parse, line_ending, Tree::parse)(r)))
+        .collect()
+}
+
 pub fn part_one(input: &str) -> Option<usize> {
-    let mut lines: Vec<&str> = input.split('\n').collect();
-    if lines.len() % 3 != 0 {
-        lines.push("");
-        assert_eq!(lines.len() % 3, 0);
-    }
-    /* let data: Vec<(Tree, Tree)> = */
+    let pairs = parse_pairs(input).unwrap_or_else(|err| panic!("Failed parsing packets: {err}"));
     Some(
-        lines
+        pairs
             .into_iter()
-            .array_chunks::<3>()
-            .map(|[l1, l2, _l3]| (Tree::from(l1), Tree::from(l2)))
             .enumerate()
             .filter(|(_i, (t1, t2))| t1 < t2)
             .fold(0, |acc, (i, _t)| acc + i + 1),
@@ -147,6 +146,24 @@ mod tests {
         assert_eq!(part_one(&input), Some(13));
     }
 
+    #[test]
+    fn test_parse_pairs() {
+        let pairs = parse_pairs("[1]\n[2]\n\n[[]]\n3\n").unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(
+            parse_pairs("[1] \r\n[2]\t\n\n[[]] \n3 \n"),
+            Ok(pairs.clone())
+        );
+        assert_eq!(
+            pairs[1],
+            (Tree::Branch(vec![Tree::Branch(vec![])]), Tree::Leaf(3))
+        );
+        let err = parse_pairs("[1]\n[2]\n\n[1]\n[2]\n[3]\n").unwrap_err();
+        assert_eq!(err.line, 5);
+        let err = parse_pairs("[1]\n[2]\n\n[1]\n").unwrap_err();
+        assert_eq!(err.line, 4);
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 13);
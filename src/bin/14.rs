@@ -1,11 +1,19 @@
 /*
-Release ->
 🎄 Part 1 🎄
-625 (elapsed: 267.99ms)
+625
 🎄 Part 2 🎄
-25193 (elapsed: 29.43s) // SHAME!
+25193
 */
 
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use advent_of_code::{
+    helpers::{Point, ShiftedGrid},
+    ANSI_CLEAR,
+};
 use nom::{
     bytes::complete::tag, character::complete::digit1, combinator::map, multi::separated_list1,
     sequence::tuple, IResult,
@@ -17,25 +25,13 @@ const SAND_ORIGIN: (usize, usize) = (500, 0);
 struct RockLine(Vec<(usize, usize)>);
 
 impl RockLine {
-    fn covers(&self, p: (usize, usize)) -> bool {
-        for points in self.0.windows(2) {
-            let p1 = points.first().unwrap();
-            let p2 = points.get(1).unwrap();
-            if p.1 == p1.1 && p1.1 == p2.1 {
-                let mi = p1.0.min(p2.0);
-                let ma = p1.0.max(p2.0);
-                if mi <= p.0 && p.0 <= ma {
-                    return true;
-                };
-            } else if p.0 == p1.0 && p1.0 == p2.0 {
-                let mi = p1.1.min(p2.1);
-                let ma = p1.1.max(p2.1);
-                if mi <= p.1 && p.1 <= ma {
-                    return true;
-                };
-            }
-        }
-        false
+    /// Every point covered by the line.
+    fn points(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.0.windows(2).flat_map(|points| {
+            let (p1, p2) = (points[0], points[1]);
+            (p1.0.min(p2.0)..=p1.0.max(p2.0))
+                .flat_map(move |x| (p1.1.min(p2.1)..=p1.1.max(p2.1)).map(move |y| (x, y)))
+        })
     }
     fn lowest(&self) -> usize {
         self.0
@@ -43,12 +39,20 @@ impl RockLine {
             .fold(0, |acc, p| acc.max(p[0].1).max(p[1].1))
     }
 }
-#[derive(Debug)]
+
+fn to_point(p: (usize, usize)) -> Point {
+    Point(p.0 as i32, p.1 as i32)
+}
+
 struct Cave {
     rock_lines: Vec<RockLine>,
     lowest_rock: usize,
     floor: Option<usize>,
     sand: Vec<(usize, usize)>,
+    /// Rocks and settled sand, over every point sand can reach even with a floor.
+    occupied: ShiftedGrid<bool>,
+    /// Path of the last grain, the next one follows it until its before last step.
+    path: Vec<(usize, usize)>,
 }
 
 impl Cave {
@@ -68,57 +72,42 @@ impl Cave {
         )(line)
     }
     fn is_free(&self, p: (usize, usize)) -> bool {
-        for rl in &self.rock_lines {
-            if rl.covers(p) {
-                return false;
-            }
-        }
-
-        for s in &self.sand {
-            if *s == p {
-                return false;
-            }
-        }
-
         if let Some(floor) = self.floor {
             if p.1 == floor {
                 return false;
             }
         }
-
-        true
+        !self.occupied.get(&to_point(p))
     }
     fn sand_fall(&mut self) -> Option<(usize, usize)> {
-        if self.sand.contains(&SAND_ORIGIN) {
-            return None;
-        }
-        let mut new_sand = SAND_ORIGIN;
         loop {
+            // source is blocked
+            let new_sand = *self.path.last()?;
             // fell too low
             if self.floor.is_none() && new_sand.1 > self.lowest_rock {
                 return None;
             }
-            // down
-            else if self.is_free((new_sand.0, new_sand.1 + 1)) {
-                new_sand.1 += 1;
-            }
-            // down left
-            else if self.is_free((new_sand.0 - 1, new_sand.1 + 1)) {
-                new_sand.0 -= 1;
-                new_sand.1 += 1;
-            }
-            // down right
-            else if self.is_free((new_sand.0 + 1, new_sand.1 + 1)) {
-                new_sand.0 += 1;
-                new_sand.1 += 1;
-            }
-            // stopped mooving
-            else {
-                break;
+            let next = [
+                // down
+                (new_sand.0, new_sand.1 + 1),
+                // down left
+                (new_sand.0 - 1, new_sand.1 + 1),
+                // down right
+                (new_sand.0 + 1, new_sand.1 + 1),
+            ]
+            .into_iter()
+            .find(|&p| self.is_free(p));
+            match next {
+                Some(p) => self.path.push(p),
+                // stopped mooving
+                None => {
+                    self.path.pop();
+                    self.occupied.set(&to_point(new_sand), true);
+                    self.sand.push(new_sand);
+                    return Some(new_sand);
+                }
             }
         }
-        self.sand.push(new_sand);
-        Some(new_sand)
     }
 }
 
@@ -129,15 +118,170 @@ impl From<&str> for Cave {
             .map(|l| Self::parse_rock(l).unwrap().1)
             .collect();
         let lowest_floor = rock_lines.iter().map(RockLine::lowest).max().unwrap();
+        let ((min_x, min_y), (max_x, max_y)) =
+            reachable_bounds(&rock_lines, Some(lowest_floor + 2));
+        let mut occupied =
+            ShiftedGrid::new(to_point((min_x, min_y)), to_point((max_x, max_y)), false);
+        rock_lines
+            .iter()
+            .flat_map(RockLine::points)
+            .for_each(|p| occupied.set(&to_point(p), true));
         Cave {
             rock_lines,
             sand: Vec::new(),
             lowest_rock: lowest_floor,
             floor: None,
+            occupied,
+            path: vec![SAND_ORIGIN],
+        }
+    }
+}
+
+/// Top-left and bottom-right corners of the area sand can reach, both included.
+fn reachable_bounds(
+    rock_lines: &[RockLine],
+    floor: Option<usize>,
+) -> ((usize, usize), (usize, usize)) {
+    let lowest_rock = rock_lines.iter().map(RockLine::lowest).max().unwrap_or(0);
+    let rock_points = rock_lines.iter().flat_map(|rl| rl.0.iter());
+    let (mut min_x, mut max_x) = rock_points.fold((SAND_ORIGIN.0, SAND_ORIGIN.0), |(mi, ma), p| {
+        (mi.min(p.0), ma.max(p.0))
+    });
+    let max_y = match floor {
+        Some(floor) => {
+            // sand piles up in a triangle on the floor
+            min_x = min_x.min(SAND_ORIGIN.0.saturating_sub(floor));
+            max_x = max_x.max(SAND_ORIGIN.0 + floor);
+            floor
+        }
+        None => lowest_rock,
+    };
+    ((min_x.saturating_sub(1), 0), (max_x + 1, max_y))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Air,
+    Rock,
+    Sand,
+    Floor,
+    Source,
+}
+
+impl Cell {
+    fn to_char(self) -> char {
+        match self {
+            Cell::Air => '.',
+            Cell::Rock => '#',
+            Cell::Sand => 'o',
+            Cell::Floor => '=',
+            Cell::Source => '+',
+        }
+    }
+    fn to_rgb(self) -> [u8; 3] {
+        match self {
+            Cell::Air => [20, 20, 30],
+            Cell::Rock => [120, 120, 120],
+            Cell::Sand => [230, 190, 90],
+            Cell::Floor => [90, 60, 40],
+            Cell::Source => [220, 40, 40],
         }
     }
 }
 
+impl Cave {
+    fn bounds(&self) -> ((usize, usize), (usize, usize)) {
+        reachable_bounds(&self.rock_lines, self.floor)
+    }
+    /// Rows of cells within `bounds()`.
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds();
+        let mut cells = vec![vec![Cell::Air; max_x - min_x + 1]; max_y - min_y + 1];
+        let mut set = |p: (usize, usize), cell: Cell| cells[p.1 - min_y][p.0 - min_x] = cell;
+        for p in self.rock_lines.iter().flat_map(RockLine::points) {
+            set(p, Cell::Rock);
+        }
+        if let Some(floor) = self.floor {
+            for x in min_x..=max_x {
+                set((x, floor), Cell::Floor);
+            }
+        }
+        set(SAND_ORIGIN, Cell::Source);
+        for &s in &self.sand {
+            set(s, Cell::Sand);
+        }
+        cells
+    }
+    fn render_ascii(&self) -> String {
+        self.cells()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(Cell::to_char)
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect()
+    }
+    /// Binary PPM (P6) image, each cell drawn as a `scale` x `scale` square.
+    fn write_ppm(&self, out: &mut impl Write, scale: usize) -> io::Result<()> {
+        let cells = self.cells();
+        let (height, width) = (cells.len() * scale, cells[0].len() * scale);
+        write!(out, "P6\n{width} {height}\n255\n")?;
+        for row in &cells {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|cell| std::iter::repeat_n(cell.to_rgb(), scale))
+                .flatten()
+                .collect();
+            for _ in 0..scale {
+                out.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Captures the cave every `every` grains (and once all sand has settled),
+/// as ASCII frames in the terminal and/or numbered PPM images.
+struct Recorder {
+    every: usize,
+    ascii: bool,
+    ppm_prefix: Option<String>,
+    scale: usize,
+    n_frame: usize,
+}
+
+impl Recorder {
+    fn capture(&mut self, cave: &Cave) -> io::Result<()> {
+        if self.ascii {
+            println!(
+                "{ANSI_CLEAR}grains: {}\n{}",
+                cave.sand.len(),
+                cave.render_ascii()
+            );
+        }
+        if let Some(prefix) = &self.ppm_prefix {
+            let path = format!("{prefix}_{:05}.ppm", self.n_frame);
+            self.write_ppm_to(cave, &path)?;
+        }
+        self.n_frame += 1;
+        Ok(())
+    }
+    fn write_ppm_to(&self, cave: &Cave, path: &str) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        cave.write_ppm(&mut out, self.scale)
+    }
+    fn run(&mut self, cave: &mut Cave) -> io::Result<()> {
+        while cave.sand_fall().is_some() {
+            if cave.sand.len().is_multiple_of(self.every) {
+                self.capture(cave)?;
+            }
+        }
+        self.capture(cave)
+    }
+}
+
 pub fn part_one(input: &str) -> Option<usize> {
     let mut cave = Cave::from(input);
     while cave.sand_fall().is_some() {}
@@ -154,9 +298,27 @@ pub fn part_two(input: &str) -> Option<usize> {
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 14);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let ascii = args.contains("--render");
+    let ppm_prefix: Option<String> = args.opt_value_from_str("--ppm").unwrap();
+    if ascii || ppm_prefix.is_some() {
+        let mut cave = Cave::from(input.as_str());
+        if args.contains("--floor") {
+            cave.set_floor();
+        }
+        let mut recorder = Recorder {
+            every: advent_of_code::opt_arg_in(&mut args, "--every", 1..).unwrap_or(usize::MAX),
+            ascii,
+            ppm_prefix,
+            scale: advent_of_code::opt_arg_in(&mut args, "--scale", 1..).unwrap_or(4),
+            n_frame: 0,
+        };
+        recorder.run(&mut cave).expect("Could not render the cave");
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +331,50 @@ mod tests {
         assert_eq!(part_one(&input), Some(24));
     }
 
+    #[test]
+    fn test_render_ascii() {
+        let input = advent_of_code::read_file("examples", 14);
+        let mut cave = Cave::from(input.as_str());
+        while cave.sand_fall().is_some() {}
+        assert_eq!(
+            cave.render_ascii(),
+            "\
+.......+....
+............
+.......o....
+......ooo...
+.....#ooo##.
+....o#ooo#..
+...###ooo#..
+.....oooo#..
+..o.ooooo#..
+.#########..
+"
+        );
+    }
+
+    #[test]
+    fn test_wide_cave() {
+        let input = "0,2 -> 0,4\n498,600 -> 502,600";
+        let cave = Cave::from(input);
+        assert_eq!(cave.bounds(), ((0, 0), (503, 600)));
+        assert_eq!(part_one(input), Some(4));
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let input = advent_of_code::read_file("examples", 14);
+        let mut cave = Cave::from(input.as_str());
+        cave.set_floor();
+        let mut ppm = Vec::new();
+        cave.write_ppm(&mut ppm, 2).unwrap();
+        let ((min_x, _), (max_x, max_y)) = cave.bounds();
+        let (width, height) = ((max_x - min_x + 1) * 2, (max_y + 1) * 2);
+        let header = format!("P6\n{width} {height}\n255\n");
+        assert!(ppm.starts_with(header.as_bytes()));
+        assert_eq!(ppm.len(), header.len() + width * height * 3);
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 14);
@@ -12,7 +12,10 @@
 12274327017867 (elapsed: 2.02s)
 */
 
-use advent_of_code::helpers::{Point, Within};
+use advent_of_code::{
+    helpers::{IntervalSet, Point, Within},
+    Params,
+};
 use nom::{
     bytes::complete::tag,
     character::complete::digit1,
@@ -20,7 +23,7 @@ use nom::{
     sequence::{preceded, tuple},
     IResult,
 };
-use std::{collections::BTreeSet, iter::once};
+use std::ops::RangeInclusive;
 
 trait TuningFrequency {
     fn tuning_frequency(&self) -> u128;
@@ -60,7 +63,8 @@ impl Sensor {
     fn covers_point(&self, p: &Point) -> bool {
         self.pos.mdist(p) <= self.range()
     }
-    fn covers_at(&self, y: i32) -> Option<BTreeSet<Point>> {
+    /// Range of `x` covered on row `y`.
+    fn covers_at(&self, y: i32) -> Option<RangeInclusive<i64>> {
         let range = self.range();
         if !y.within(&(self.pos.y() - range), &(self.pos.y() + range)) {
             return None;
@@ -74,16 +78,8 @@ impl Sensor {
 
         assert!(rab >= 0);
 
-        let base_x = self.pos.x();
-
-        let mut cover: BTreeSet<Point> = (1..=rab)
-            .flat_map(|dx| [Point(base_x + dx, y), Point(base_x - dx, y)])
-            .chain(once(Point(base_x, y)))
-            .collect();
-
-        cover.remove(&self.closest_beacon);
-
-        Some(cover)
+        let base_x = self.pos.x() as i64;
+        Some(base_x - rab as i64..=base_x + rab as i64)
     }
 
     // fn get_border<'a>(&'a self) -> impl Iterator<Item = Point> + 'a {
@@ -130,23 +126,26 @@ impl From<&str> for Sensor {
     }
 }
 
-pub fn inner_part_one(input: &str, line: usize) -> usize {
-    let cover = input
-        .lines()
-        .map(Sensor::from)
-        .filter_map(|s| s.covers_at(line as i32))
-        .fold(BTreeSet::new(), |mut acc, mut bt| {
-            acc.append(&mut bt);
-            acc
-        });
-    cover.len()
-}
-
-pub fn part_one(input: &str) -> Option<usize> {
-    Some(inner_part_one(input, 2_000_000))
+/// Keys of the params files: the scanned row and the search area limit.
+const PARAMS: &[&str] = &["row", "limit"];
+
+pub fn part_one(input: &str, params: &Params) -> Option<u128> {
+    let line: i32 = params.get("row", 2_000_000);
+    let mut cover = IntervalSet::new();
+    for sensor in input.lines().map(Sensor::from) {
+        if let Some(range) = sensor.covers_at(line) {
+            let mut sensor_cover = IntervalSet::from(range);
+            if sensor.closest_beacon.y() == line {
+                sensor_cover.remove(sensor.closest_beacon.x() as i64);
+            }
+            cover.merge(&sensor_cover);
+        }
+    }
+    Some(cover.len())
 }
 
-pub fn inner_part_two(input: &str, limit: i32) -> u128 {
+pub fn part_two(input: &str, params: &Params) -> Option<u128> {
+    let limit: i32 = params.get("limit", 4_000_000);
     let sensors: Vec<Sensor> = input.lines().map(Sensor::from).collect();
     for sensor in &sensors {
         let p = sensor.get_border().find(|p| {
@@ -161,20 +160,17 @@ pub fn inner_part_two(input: &str, limit: i32) -> u128 {
             true
         });
         if let Some(p) = p {
-            return p.tuning_frequency();
+            return Some(p.tuning_frequency());
         }
     }
-    0
-}
-
-pub fn part_two(input: &str) -> Option<u128> {
-    Some(inner_part_two(input, 4_000_000))
+    None
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", 15);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let params = &advent_of_code::read_params("inputs", 15, PARAMS);
+    advent_of_code::solve!(1, part_one, input, params);
+    advent_of_code::solve!(2, part_two, input, params);
 }
 
 #[cfg(test)]
@@ -187,10 +183,7 @@ mod tests {
             closest_beacon: Point(10, 5),
             pos: Point(10, 0),
         };
-        assert_eq!(
-            s.covers_at(4),
-            Some(BTreeSet::from([Point(10, 4), Point(11, 4), Point(9, 4)]))
-        )
+        assert_eq!(s.covers_at(4), Some(9..=11))
     }
 
     #[test]
@@ -199,21 +192,22 @@ mod tests {
             closest_beacon: Point(10, 5),
             pos: Point(10, 10),
         };
-        assert_eq!(
-            s.covers_at(6),
-            Some(BTreeSet::from([Point(10, 6), Point(11, 6), Point(9, 6)]))
-        )
+        assert_eq!(s.covers_at(6), Some(9..=11));
+        assert_eq!(s.covers_at(5), Some(10..=10));
+        assert_eq!(s.covers_at(4), None)
     }
 
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(inner_part_one(&input, 10), 26);
+        let params = advent_of_code::read_params("examples", 15, PARAMS);
+        assert_eq!(part_one(&input, &params), Some(26));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 15);
-        assert_eq!(inner_part_two(&input, 20), 56000011);
+        let params = advent_of_code::read_params("examples", 15, PARAMS);
+        assert_eq!(part_two(&input, &params), Some(56000011));
     }
 }
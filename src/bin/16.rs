@@ -1,13 +1,18 @@
 /*
-
--> Release:
 🎄 Part 1 🎄
-1659 (elapsed: 52.94ms)
+1659
 🎄 Part 2 🎄
-2382 (elapsed: 51.61s) :/
-
+2382
 */
-use std::collections::{HashMap, VecDeque};
+use std::{cmp::Reverse, collections::HashMap, fmt::Display};
+
+use advent_of_code::{
+    helpers::{
+        graph::{CompressedGraph, LabelledGraph, UNREACHABLE},
+        BitSet64,
+    },
+    Params,
+};
 
 use nom::{
     branch::alt,
@@ -57,103 +62,250 @@ impl From<&str> for Cave {
     }
 }
 
+const START: Label = ['A', 'A'];
+
 #[derive(Debug)]
 struct CaveSystem {
-    caves: HashMap<Label, Cave>,
-    all_dists: HashMap<Label, HashMap<Label, usize>>,
+    /// Start cave and caves with a non-zero flow, indexed densely.
+    graph: CompressedGraph<Label>,
+    flows: Vec<usize>,
+    start: usize,
 }
 
 impl CaveSystem {
-    fn dists_bfs(&self, label: Label) -> HashMap<Label, usize> {
-        let mut dists = HashMap::new();
-        dists.insert(label, 0);
-        let mut to_visit = VecDeque::new();
-        to_visit.push_front((&label, 0));
-        while let Some((l, d)) = to_visit.pop_front() {
-            for neigh in &self.caves.get(l).unwrap().con_cave {
-                if dists.contains_key(neigh) {
-                    continue;
-                }
-                to_visit.push_back((neigh, d + 1));
-                dists.insert(*neigh, d + 1);
-            }
-        }
-        dists
-            .into_iter()
-            .filter(|(l, _c)| self.caves.get(l).unwrap().valve_flow != 0)
-            .collect()
-    }
     fn new(caves: impl Iterator<Item = Cave>) -> Self {
-        let mut caves_map = HashMap::new();
+        let mut graph = LabelledGraph::new();
+        let mut flows = HashMap::new();
         caves.for_each(|c| {
-            caves_map.insert(c.label, c);
+            graph.add_node(c.label);
+            for neigh in c.con_cave {
+                graph.add_edge(c.label, neigh);
+            }
+            flows.insert(c.label, c.valve_flow as usize);
         });
-        let mut cs = CaveSystem {
-            caves: caves_map,
-            all_dists: HashMap::new(),
-        };
+        let graph = graph.compress(|l| *l == START || flows[l] != 0);
+        let flows = graph.labels().iter().map(|l| flows[l]).collect();
+        let start = graph.index_of(&START).expect("No start cave");
+        CaveSystem {
+            graph,
+            flows,
+            start,
+        }
+    }
+}
 
-        for (&l1, _c1) in cs.caves.iter() {
-            cs.all_dists.insert(l1, cs.dists_bfs(l1));
+/// A valve opened at `minute` (counted from the start of the walk).
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    minute: usize,
+    valve: Label,
+    released: usize,
+}
+
+/// Order in which valves are opened, with the total released pressure.
+#[derive(Debug, Clone, Default)]
+struct Plan {
+    pressure: usize,
+    steps: Vec<Step>,
+}
+
+impl Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            writeln!(
+                f,
+                "  minute {:>2}: open {}{} (+{})",
+                step.minute, step.valve[0], step.valve[1], step.released
+            )?;
         }
-        cs
+        write!(f, "  total: {}", self.pressure)
     }
 }
 
-fn get_pressure(
-    label: &Label,
-    time: usize,
-    op_valves: Vec<Label>,
-    cave_system: &CaveSystem,
-    elephant: Option<()>,
-) -> usize {
-    let mut pressure = 0;
-    let mut other_caves: Vec<(&Label, &usize)> = cave_system
-        .all_dists
-        .get(label)
-        .unwrap()
-        .iter()
-        .filter(|(&othr_label, &d)| {
-            !op_valves.contains(&othr_label)
-                && time > d + 1
-        })
-        .collect();
-    other_caves.sort_by(|(&l1, &d1), (&l2, &d2)| {
-        (time.saturating_sub(d1) * cave_system.caves.get(&l1).unwrap().valve_flow as usize).cmp(
-            &(time.saturating_sub(d2) * cave_system.caves.get(&l2).unwrap().valve_flow as usize),
-        )
-    });
-    for (other_label, dist_from_label) in other_caves {
-        let new_time = time - (dist_from_label + 1);
-            let mut new_op_valves = op_valves.clone();
-            new_op_valves.push(*other_label);
-            pressure = pressure.max(
-                new_time * cave_system.caves.get(other_label).unwrap().valve_flow as usize
-                    + get_pressure(other_label, new_time, new_op_valves, cave_system, elephant),
+impl CaveSystem {
+    /// Best released pressure for every set of valves that can be opened within `time` minutes.
+    fn best_pressures(&self, time: usize) -> HashMap<BitSet64, usize> {
+        let mut best = HashMap::new();
+        let mut seen = HashMap::new();
+        self.explore(self.start, time, BitSet64::new(), 0, &mut best, &mut seen);
+        best
+    }
+
+    /// Walks every order of opening valves, skipping states already reached with at
+    /// least as much pressure: from the same state, the same valves are left to open.
+    fn explore(
+        &self,
+        cave: usize,
+        time_left: usize,
+        opened: BitSet64,
+        pressure: usize,
+        best: &mut HashMap<BitSet64, usize>,
+        seen: &mut HashMap<(usize, usize, BitSet64), usize>,
+    ) {
+        let state = (cave, time_left, opened);
+        if seen
+            .get(&state)
+            .is_some_and(|&seen_pressure| seen_pressure >= pressure)
+        {
+            return;
+        }
+        seen.insert(state, pressure);
+        let entry = best.entry(opened).or_insert(0);
+        *entry = (*entry).max(pressure);
+        for (other_cave, &dist) in self.graph.dists().row(cave).iter().enumerate() {
+            if self.flows[other_cave] == 0
+                || opened.contains(other_cave)
+                || dist == UNREACHABLE
+                || time_left <= dist + 1
+            {
+                continue;
+            }
+            let new_time_left = time_left - (dist + 1);
+            self.explore(
+                other_cave,
+                new_time_left,
+                opened.with(other_cave),
+                pressure + new_time_left * self.flows[other_cave],
+                best,
+                seen,
+            );
+        }
+    }
+
+    /// Best order in which to open exactly the valves of `valves` within `time` minutes.
+    fn plan(&self, time: usize, valves: BitSet64) -> Plan {
+        let mut best = Plan::default();
+        self.plan_rec(
+            self.start,
+            time,
+            time,
+            valves,
+            &mut Plan::default(),
+            &mut best,
+        );
+        best
+    }
+
+    fn plan_rec(
+        &self,
+        cave: usize,
+        total_time: usize,
+        time_left: usize,
+        to_open: BitSet64,
+        current: &mut Plan,
+        best: &mut Plan,
+    ) {
+        if to_open.is_empty() {
+            if current.pressure >= best.pressure {
+                *best = current.clone();
+            }
+            return;
+        }
+        for other_cave in to_open.iter() {
+            let dist = self.graph.dist(cave, other_cave);
+            if dist == UNREACHABLE || time_left <= dist + 1 {
+                continue;
+            }
+            let new_time_left = time_left - (dist + 1);
+            let released = new_time_left * self.flows[other_cave];
+            current.pressure += released;
+            current.steps.push(Step {
+                minute: total_time - new_time_left,
+                valve: self.graph.label(other_cave),
+                released,
+            });
+            let mut still_to_open = to_open;
+            still_to_open.remove(other_cave);
+            self.plan_rec(
+                other_cave,
+                total_time,
+                new_time_left,
+                still_to_open,
+                current,
+                best,
             );
+            current.steps.pop();
+            current.pressure -= released;
+        }
     }
-    if elephant.is_some() {
-        pressure = pressure.max(get_pressure(&['A', 'A'], 26, op_valves, cave_system, None));
+
+    fn best_opened_set(&self, time: usize) -> (BitSet64, usize) {
+        self.best_pressures(time)
+            .into_iter()
+            .max_by_key(|&(_, pressure)| pressure)
+            .unwrap_or_default()
     }
-    pressure
+
+    /// Best pair of disjoint sets of valves for you and the elephant to open,
+    /// with the total released pressure.
+    fn best_split(&self, time: usize) -> (BitSet64, BitSet64, usize) {
+        let mut pressures: Vec<(BitSet64, usize)> = self.best_pressures(time).into_iter().collect();
+        pressures.sort_by_key(|&(_, pressure)| Reverse(pressure));
+        let mut best = (BitSet64::new(), BitSet64::new(), 0);
+        for (i, &(mask1, pressure1)) in pressures.iter().enumerate() {
+            if pressure1 * 2 < best.2 {
+                break;
+            }
+            for &(mask2, pressure2) in pressures.iter().skip(i) {
+                if pressure1 + pressure2 <= best.2 {
+                    break;
+                }
+                if mask1.is_disjoint(&mask2) {
+                    best = (mask1, mask2, pressure1 + pressure2);
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Keys of the params files.
+const PARAMS: &[&str] = &["minutes", "minutes_with_elephant"];
+
+fn minutes(params: &Params) -> usize {
+    params.get("minutes", 30)
+}
+
+/// Minutes left once the elephant has been taught.
+fn minutes_with_elephant(params: &Params) -> usize {
+    params.get("minutes_with_elephant", 26)
+}
+
+pub fn part_one(input: &str, params: &Params) -> Option<usize> {
+    let cave_system = CaveSystem::new(input.lines().map(Cave::from));
+    Some(cave_system.best_opened_set(minutes(params)).1)
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
+pub fn part_two(input: &str, params: &Params) -> Option<usize> {
     let cave_system = CaveSystem::new(input.lines().map(Cave::from));
-    let result = get_pressure(&['A', 'A'], 30, Vec::new(), &cave_system, None);
-    Some(result)
+    Some(cave_system.best_split(minutes_with_elephant(params)).2)
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
+fn print_schedules(input: &str, params: &Params) {
     let cave_system = CaveSystem::new(input.lines().map(Cave::from));
-    let result = get_pressure(&['A', 'A'], 26, Vec::new(), &cave_system, Some(()));
-    Some(result)
+    let (time, time_with_elephant) = (minutes(params), minutes_with_elephant(params));
+    let (valves, _) = cave_system.best_opened_set(time);
+    println!("Part 1 schedule:\n{}", cave_system.plan(time, valves));
+    let (you, elephant, _) = cave_system.best_split(time_with_elephant);
+    println!(
+        "Part 2 schedule (you):\n{}",
+        cave_system.plan(time_with_elephant, you)
+    );
+    println!(
+        "Part 2 schedule (elephant):\n{}",
+        cave_system.plan(time_with_elephant, elephant)
+    );
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 16);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let params = &advent_of_code::read_params("inputs", 16, PARAMS);
+    advent_of_code::solve!(1, part_one, input, params);
+    advent_of_code::solve!(2, part_two, input, params);
+    if args.contains("--schedule") {
+        print_schedules(input, params);
+    }
 }
 
 #[cfg(test)]
@@ -163,12 +315,50 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_one(&input), Some(1651));
+        let params = advent_of_code::read_params("examples", 16, PARAMS);
+        assert_eq!(part_one(&input, &params), Some(1651));
+    }
+
+    #[test]
+    fn test_split_plans() {
+        let input = advent_of_code::read_file("examples", 16);
+        let cave_system = CaveSystem::new(input.lines().map(Cave::from));
+        let (you, elephant, pressure) = cave_system.best_split(26);
+        let you = cave_system.plan(26, you);
+        let elephant = cave_system.plan(26, elephant);
+        assert_eq!(you.pressure + elephant.pressure, pressure);
+        let opened = |plan: &Plan| {
+            let mut valves: Vec<Label> = plan.steps.iter().map(|s| s.valve).collect();
+            valves.sort();
+            valves
+        };
+        let mut all = [opened(&you), opened(&elephant)];
+        all.sort();
+        assert_eq!(
+            all,
+            [
+                vec![['B', 'B'], ['C', 'C'], ['J', 'J']],
+                vec![['D', 'D'], ['E', 'E'], ['H', 'H']]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_valve() {
+        let input = "\
+Valve AA has flow rate=0; tunnels lead to valves BB
+Valve BB has flow rate=5; tunnels lead to valves AA
+Valve CC has flow rate=100; tunnels lead to valves DD
+Valve DD has flow rate=0; tunnel leads to valve CC
+";
+        assert_eq!(part_one(input, &Params::default()), Some(140));
+        assert_eq!(part_two(input, &Params::default()), Some(120));
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 16);
-        assert_eq!(part_two(&input), Some(1707));
+        let params = advent_of_code::read_params("examples", 16, PARAMS);
+        assert_eq!(part_two(&input, &params), Some(1707));
     }
 }
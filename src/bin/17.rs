@@ -5,39 +5,101 @@
 🎄 Part 2 🎄
 1535483870924 (elapsed: 16.85ms)
 */
-use advent_of_code::btree_set;
+use advent_of_code::{helpers::cycle, ANSI_CLEAR};
 use std::{
-    collections::{hash_map::Entry, BTreeSet, HashMap},
-    fmt::{Debug, Write},
+    collections::BTreeSet,
+    fmt::{Debug, Write as _},
+    fs::File,
+    io::Write,
+    thread,
+    time::Duration,
 };
 
-#[derive(Debug)]
-enum RockShape {
-    Hori,
-    Cross,
-    Angle,
-    Verti,
-    Square,
+/// The five rocks of the puzzle, top row first, separated by blank lines.
+const DEFAULT_ROCKS: &str = "\
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##";
+
+#[derive(Debug, Clone)]
+struct RockShape {
+    /// `(dx, dy)` from the top-left corner, `dy` going down.
+    points: Vec<(usize, usize)>,
+    width: usize,
+    height: usize,
 }
 
 impl RockShape {
     fn get_points(&self) -> Vec<(usize, usize)> {
-        match self {
-            RockShape::Hori => vec![(0, 0), (1, 0), (2, 0), (3, 0)],
-            RockShape::Cross => vec![(1, 0), (1, 1), (1, 2), (0, 1), (2, 1)],
-            RockShape::Angle => vec![(0, 2), (1, 2), (2, 2), (2, 1), (2, 0)],
-            RockShape::Verti => vec![(0, 0), (0, 1), (0, 2), (0, 3)],
-            RockShape::Square => vec![(0, 0), (0, 1), (1, 0), (1, 1)],
-        }
+        self.points.clone()
     }
 
     fn get_width_height(&self) -> (usize, usize) {
-        match self {
-            RockShape::Hori => (4, 1),
-            RockShape::Cross => (3, 3),
-            RockShape::Angle => (3, 3),
-            RockShape::Verti => (1, 4),
-            RockShape::Square => (2, 2),
+        (self.width, self.height)
+    }
+}
+
+impl From<&str> for RockShape {
+    fn from(drawing: &str) -> Self {
+        let points: Vec<(usize, usize)> = drawing
+            .lines()
+            .enumerate()
+            .flat_map(|(dy, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(dx, _)| (dx, dy))
+            })
+            .collect();
+        assert!(!points.is_empty(), "Empty rock drawing");
+        RockShape {
+            width: points.iter().map(|p| p.0).max().unwrap() + 1,
+            height: points.iter().map(|p| p.1).max().unwrap() + 1,
+            points,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TetrisConfig {
+    width: usize,
+    rocks: Vec<RockShape>,
+    /// Free columns between the left wall and a new rock.
+    spawn_left: usize,
+    /// Free rows between the highest rock and the bottom of a new rock.
+    spawn_above: usize,
+}
+
+impl TetrisConfig {
+    fn parse_rocks(drawings: &str) -> Vec<RockShape> {
+        drawings
+            .split("\n\n")
+            .map(|drawing| RockShape::from(drawing.trim_matches('\n')))
+            .collect()
+    }
+}
+
+impl Default for TetrisConfig {
+    fn default() -> Self {
+        TetrisConfig {
+            width: 7,
+            rocks: TetrisConfig::parse_rocks(DEFAULT_ROCKS),
+            spawn_left: 2,
+            spawn_above: 3,
         }
     }
 }
@@ -50,24 +112,28 @@ enum HotJet {
 
 struct NotTetris {
     grid: BTreeSet<(usize, usize)>,
-    rocks: [RockShape; 5],
+    config: TetrisConfig,
     hot_jets: Vec<HotJet>,
     highest_rock: (usize, usize),
+    rock_index: usize,
+    jet_index: usize,
 }
 
 impl NotTetris {
     fn new(hot_jet_input: &str) -> Self {
-        let rocks = [
-            RockShape::Hori,
-            RockShape::Cross,
-            RockShape::Angle,
-            RockShape::Verti,
-            RockShape::Square,
-        ];
-
-        let tetris = NotTetris {
-            grid: btree_set![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0)],
-            rocks,
+        NotTetris::with_config(hot_jet_input, TetrisConfig::default())
+    }
+    fn with_config(hot_jet_input: &str, config: TetrisConfig) -> Self {
+        assert!(
+            config
+                .rocks
+                .iter()
+                .all(|r| config.spawn_left + r.width <= config.width),
+            "Rocks do not fit in the chamber"
+        );
+        NotTetris {
+            grid: (0..config.width).map(|x| (x, 0)).collect(),
+            config,
             hot_jets: hot_jet_input
                 .trim()
                 .chars()
@@ -78,21 +144,19 @@ impl NotTetris {
                 })
                 .collect(),
             highest_rock: (0, 0),
-        };
-
-        tetris
-    }
-    fn get_top_row_relative_to_highest(&self) -> [usize; 7] {
-        let mut res = [0; 7];
-        for (x, e_res) in res.iter_mut().enumerate() {
-            let base_y = self.highest_rock.1;
-            let mut dy = 0;
-            while !self.grid.contains(&(x, base_y - dy)) {
-                dy += 1
-            }
-            *e_res = dy;
+            rock_index: 0,
+            jet_index: 0,
         }
-        res
+    }
+    fn get_top_row_relative_to_highest(&self) -> Vec<usize> {
+        let base_y = self.highest_rock.1;
+        (0..self.config.width)
+            .map(|x| {
+                (0..base_y)
+                    .find(|dy| self.grid.contains(&(x, base_y - dy)))
+                    .unwrap_or(base_y)
+            })
+            .collect()
     }
     fn try_move_hori(
         &self,
@@ -105,7 +169,7 @@ impl NotTetris {
             HotJet::L => {
                 if let Some(new_x) = ori_rock.0.checked_sub(1) {
                     for p in rock_points {
-                        if self.grid.get(&(new_x + p.0, ori_rock.1 - p.1)).is_some() {
+                        if self.grid.contains(&(new_x + p.0, ori_rock.1 - p.1)) {
                             return None;
                         }
                     }
@@ -113,12 +177,11 @@ impl NotTetris {
                 }
             }
             HotJet::R => {
-                if ori_rock.0 + 1 + (width - 1) < 7 {
+                if ori_rock.0 + 1 + (width - 1) < self.config.width {
                     for p in rock_points {
                         if self
                             .grid
-                            .get(&(ori_rock.0 + 1 + p.0, ori_rock.1 - p.1))
-                            .is_some()
+                            .contains(&(ori_rock.0 + 1 + p.0, ori_rock.1 - p.1))
                         {
                             return None;
                         }
@@ -137,101 +200,97 @@ impl NotTetris {
     ) -> Option<(usize, usize)> {
         let new_ori = (ori_rock.0, ori_rock.1 - 1);
         for p in rock_points {
-            if self.grid.get(&(new_ori.0 + p.0, new_ori.1 - p.1)).is_some() {
+            if self.grid.contains(&(new_ori.0 + p.0, new_ori.1 - p.1)) {
                 return None;
             }
         }
         Some(new_ori)
     }
-    fn fall_rocks(&mut self, n: usize) {
-        let mut rocks_iter = self.rocks.iter().enumerate().cycle();
-        let mut hot_jets_iter = self.hot_jets.iter().enumerate().cycle();
-        let mut precedent = HashMap::new();
-        let mut rock_just_poped;
-        let mut _i = 0;
-        while _i < n {
-            let (rock_index, nrock) = rocks_iter.next().unwrap();
-            let (width, height) = nrock.get_width_height();
-            let nrock_points = nrock.get_points();
-            let mut origin_nrock = (2, self.highest_rock.1 + 3 + height);
-            rock_just_poped = true;
-            loop {
-                let (jet_index, next_jet) = hot_jets_iter.next().expect("No hot jets parsed...");
-                if rock_just_poped {
-                    if let Entry::Vacant(e) = precedent.entry((
-                        rock_index,
-                        jet_index,
-                        self.get_top_row_relative_to_highest(),
-                    )) {
-                        e.insert((_i, self.highest_rock.1));
-                    } else {
-                        // period found !
-                        let (oldi, old_highest) = precedent
-                            .get(&(
-                                rock_index,
-                                jet_index,
-                                self.get_top_row_relative_to_highest(),
-                            ))
-                            .unwrap();
-                        // compute the number of period we can foresee
-                        let n_periods = (n - _i) / (_i - oldi);
-
-                        _i += n_periods * (_i - oldi);
-                        let new_highest =
-                            self.highest_rock.1 + (self.highest_rock.1 - old_highest) * n_periods;
-
-                        for (x, dy) in self
-                            .get_top_row_relative_to_highest()
-                            .into_iter()
-                            .enumerate()
-                        {
-                            self.grid.insert((x, new_highest - dy));
-                            if dy == 0 {
-                                self.highest_rock = (x, new_highest);
-                            }
-                        }
-                    }
-                    // origin might have changed
-                    origin_nrock = (2, self.highest_rock.1 + 3 + height);
-                    rock_just_poped = false;
-                }
-                if let Some(new_ori) =
-                    self.try_move_hori(origin_nrock, (width, height), &nrock_points, next_jet)
-                {
-                    origin_nrock = new_ori;
-                }
-                if let Some(new_ori) = self.try_move_down(origin_nrock, &nrock_points) {
-                    origin_nrock = new_ori;
-                } else {
-                    break;
-                }
+    fn state_key(&self) -> (usize, usize, Vec<usize>) {
+        (
+            self.rock_index,
+            self.jet_index,
+            self.get_top_row_relative_to_highest(),
+        )
+    }
+    fn fall_rock(&mut self) {
+        self.fall_rock_with(|_, _| {});
+    }
+    /// Drops the next rock, calling `on_frame` with the falling rock's points
+    /// when it spawns and after each move, and with `None` once it landed.
+    fn fall_rock_with(&mut self, mut on_frame: impl FnMut(&NotTetris, Option<&[(usize, usize)]>)) {
+        let nrock = &self.config.rocks[self.rock_index];
+        let (width, height) = nrock.get_width_height();
+        let nrock_points = nrock.get_points();
+        self.rock_index = (self.rock_index + 1) % self.config.rocks.len();
+        let mut origin_nrock = (
+            self.config.spawn_left,
+            self.highest_rock.1 + self.config.spawn_above + height,
+        );
+        let grid_points = |origin: (usize, usize)| -> Vec<(usize, usize)> {
+            nrock_points
+                .iter()
+                .map(|p| (origin.0 + p.0, origin.1 - p.1))
+                .collect()
+        };
+        on_frame(self, Some(&grid_points(origin_nrock)));
+        loop {
+            assert!(!self.hot_jets.is_empty(), "No hot jets parsed...");
+            let jet_index = self.jet_index;
+            self.jet_index = (jet_index + 1) % self.hot_jets.len();
+            if let Some(new_ori) = self.try_move_hori(
+                origin_nrock,
+                (width, height),
+                &nrock_points,
+                &self.hot_jets[jet_index],
+            ) {
+                origin_nrock = new_ori;
             }
-            let old_highest = self.highest_rock.1;
-            // landing
-            for p in nrock_points {
-                let grid_p = (origin_nrock.0 + p.0, origin_nrock.1 - p.1);
-                if self.highest_rock.1 < grid_p.1 {
-                    self.highest_rock = grid_p;
-                }
-                assert!(self.grid.insert(grid_p));
+            on_frame(self, Some(&grid_points(origin_nrock)));
+            if let Some(new_ori) = self.try_move_down(origin_nrock, &nrock_points) {
+                origin_nrock = new_ori;
+                on_frame(self, Some(&grid_points(origin_nrock)));
+            } else {
+                break;
             }
-            for y in
-                (old_highest.saturating_sub(1_000))..(self.highest_rock.1.saturating_sub(1_000))
-            {
-                for x in 0..7 {
-                    self.grid.remove(&(x, y));
-                }
+        }
+        let old_highest = self.highest_rock.1;
+        // landing
+        for grid_p in grid_points(origin_nrock) {
+            if self.highest_rock.1 < grid_p.1 {
+                self.highest_rock = grid_p;
             }
-            _i += 1;
+            assert!(self.grid.insert(grid_p));
+        }
+        for y in (old_highest.saturating_sub(1_000))..(self.highest_rock.1.saturating_sub(1_000)) {
+            for x in 0..self.config.width {
+                self.grid.remove(&(x, y));
+            }
+        }
+        on_frame(self, None);
+    }
+    fn fall_rocks(&mut self, n: usize) {
+        for _ in 0..n {
+            self.fall_rock();
         }
     }
+    /// Height of the tower after `n` rocks, skipping ahead once the falls become periodic.
+    fn tower_height_after(&mut self, n: usize) -> usize {
+        cycle::skip_ahead(
+            self,
+            NotTetris::state_key,
+            NotTetris::fall_rock,
+            |tetris| tetris.highest_rock.1,
+            n,
+        )
+    }
 }
 
 impl Debug for NotTetris {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let height = self.highest_rock.1;
         for y in (1..=height).rev() {
-            for x in 0..7 {
+            for x in 0..self.config.width {
                 f.write_char(if self.grid.contains(&(x, y)) {
                     '#'
                 } else {
@@ -241,7 +300,7 @@ impl Debug for NotTetris {
             }
             f.write_char('\n').unwrap();
         }
-        for _ in 0..7 {
+        for _ in 0..self.config.width {
             f.write_char('-').unwrap();
         }
         f.write_char('\n').unwrap();
@@ -251,6 +310,71 @@ impl Debug for NotTetris {
     }
 }
 
+impl NotTetris {
+    /// Draws the `rows` topmost rows of the chamber, with the falling rock as `@`.
+    fn render_frame(&self, falling: Option<&[(usize, usize)]>, rows: usize) -> String {
+        let falling = falling.unwrap_or_default();
+        let top = falling
+            .iter()
+            .map(|p| p.1)
+            .max()
+            .unwrap_or(0)
+            .max(self.highest_rock.1)
+            .max(rows.saturating_sub(1));
+        let mut frame = String::new();
+        for y in (top + 1 - rows.min(top + 1)..=top).rev() {
+            frame.push('|');
+            for x in 0..self.config.width {
+                frame.push(if falling.contains(&(x, y)) {
+                    '@'
+                } else if y == 0 {
+                    '-'
+                } else if self.grid.contains(&(x, y)) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            frame.push_str(&format!("| {y}\n"));
+        }
+        frame
+    }
+}
+
+struct Visualizer {
+    fps: Option<usize>,
+    frames_file: Option<File>,
+    rows: usize,
+    n_frame: usize,
+}
+
+impl Visualizer {
+    fn show(&mut self, tetris: &NotTetris, n_rock: usize, falling: Option<&[(usize, usize)]>) {
+        let frame = format!(
+            "frame {} - rock {} - next jet {} - height {}\n{}",
+            self.n_frame,
+            n_rock,
+            tetris.jet_index,
+            tetris.highest_rock.1,
+            tetris.render_frame(falling, self.rows)
+        );
+        self.n_frame += 1;
+        if let Some(file) = self.frames_file.as_mut() {
+            writeln!(file, "{frame}").expect("Could not write frame");
+        }
+        if let Some(fps) = self.fps {
+            print!("{ANSI_CLEAR}{frame}");
+            thread::sleep(Duration::from_secs_f64(1.0 / fps as f64));
+        }
+    }
+
+    fn run(&mut self, tetris: &mut NotTetris, n_rocks: usize) {
+        for n_rock in 0..n_rocks {
+            tetris.fall_rock_with(|tetris, falling| self.show(tetris, n_rock, falling));
+        }
+    }
+}
+
 pub fn part_one(input: &str) -> Option<usize> {
     let mut tetris = NotTetris::new(input);
     tetris.fall_rocks(2022);
@@ -259,19 +383,36 @@ pub fn part_one(input: &str) -> Option<usize> {
 
 pub fn part_two(input: &str) -> Option<usize> {
     let mut tetris = NotTetris::new(input);
-    tetris.fall_rocks(1_000_000_000_000);
-    Some(tetris.highest_rock.1)
+    Some(tetris.tower_height_after(1_000_000_000_000))
 }
 
+/// The `key` flag, which must be at least 1, or `default` when absent.
+/// Exits with a usage error on invalid values.
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 17);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    let visualize = args.contains("--visualize");
+    let frames_path: Option<String> = args.opt_value_from_str("--frames").unwrap();
+    if visualize || frames_path.is_some() {
+        let mut visualizer = Visualizer {
+            fps: visualize
+                .then(|| advent_of_code::opt_arg_in(&mut args, "--fps", 1..).unwrap_or(10)),
+            frames_file: frames_path.map(|path| File::create(path).expect("Could not create file")),
+            rows: advent_of_code::opt_arg_in(&mut args, "--rows", 1..).unwrap_or(20),
+            n_frame: 0,
+        };
+        let n_rocks = advent_of_code::opt_arg_in(&mut args, "--rocks", 0..).unwrap_or(10);
+        visualizer.run(&mut NotTetris::new(input), n_rocks);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::rng::Lcg;
 
     #[test]
     fn test_part_one() {
@@ -279,6 +420,76 @@ mod tests {
         assert_eq!(part_one(&input), Some(3068));
     }
 
+    #[test]
+    fn test_tower_height_after() {
+        let input = advent_of_code::read_file("examples", 17);
+        let mut brute_force = NotTetris::new(&input);
+        brute_force.fall_rocks(5_000);
+        let mut tetris = NotTetris::new(&input);
+        assert_eq!(tetris.tower_height_after(5_000), brute_force.highest_rock.1);
+    }
+
+    /// Deterministic pseudo-random jet pattern of `len` jets.
+    fn jets(seed: u64, len: usize) -> String {
+        let mut rng = Lcg::new(seed);
+        (0..len)
+            .map(|_| if rng.next_u64() >> 63 == 0 { '<' } else { '>' })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_rocks() {
+        let rocks = TetrisConfig::parse_rocks(DEFAULT_ROCKS);
+        assert_eq!(rocks.len(), 5);
+        assert_eq!(rocks[1].get_width_height(), (3, 3));
+        assert_eq!(rocks[2].get_width_height(), (3, 3));
+        assert_eq!(rocks[3].get_width_height(), (1, 4));
+        let mut angle = rocks[2].get_points();
+        angle.sort();
+        assert_eq!(angle, vec![(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_skip_ahead_variants() {
+        let configs = [
+            TetrisConfig::default(),
+            TetrisConfig {
+                width: 5,
+                spawn_left: 1,
+                ..TetrisConfig::default()
+            },
+            TetrisConfig {
+                width: 9,
+                rocks: TetrisConfig::parse_rocks("###\n\n#.\n##\n\n#\n#"),
+                spawn_left: 3,
+                spawn_above: 2,
+            },
+        ];
+        for (seed, config) in configs.into_iter().enumerate() {
+            let input = jets(seed as u64, 37 + seed * 13);
+            let mut brute_force = NotTetris::with_config(&input, config.clone());
+            brute_force.fall_rocks(4_000);
+            let mut tetris = NotTetris::with_config(&input, config);
+            assert_eq!(tetris.tower_height_after(4_000), brute_force.highest_rock.1);
+        }
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let input = advent_of_code::read_file("examples", 17);
+        let mut tetris = NotTetris::new(&input);
+        let mut frames = Vec::new();
+        tetris.fall_rock_with(|tetris, falling| frames.push(tetris.render_frame(falling, 5)));
+        assert_eq!(
+            frames[0],
+            "|..@@@@.| 4\n|.......| 3\n|.......| 2\n|.......| 1\n|-------| 0\n"
+        );
+        assert_eq!(
+            frames.last().unwrap(),
+            "|.......| 4\n|.......| 3\n|.......| 2\n|..####.| 1\n|-------| 0\n"
+        );
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", 17);
@@ -1,15 +1,13 @@
-#![feature(iter_array_chunks)]
 // 🎄 Part 1 🎄
 // 75254 (elapsed: 4.09ms)
 // 🎄 Part 2 🎄
 // 108311 (elapsed: 439.52µs)
+use advent_of_code::{ANSI_BOLD, ANSI_CLEAR, ANSI_RESET};
 use core::panic;
 use std::{
-    cell::RefCell,
-    collections::HashMap,
-    fmt::{Debug, Write},
+    fmt::{Debug, Write as _},
+    io::{self, BufRead, Write},
     ops::{Add, AddAssign, Deref, DerefMut},
-    rc::{Rc, Weak},
 };
 
 use nom::{
@@ -121,13 +119,13 @@ impl AddAssign<Turn> for Direction {
     }
 }
 
-impl Into<usize> for Direction {
-    fn into(self) -> usize {
-        match self {
-            Self::R => 0,
-            Self::D => 1,
-            Self::L => 2,
-            Self::U => 3,
+impl From<Direction> for usize {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::R => 0,
+            Direction::D => 1,
+            Direction::L => 2,
+            Direction::U => 3,
         }
     }
 }
@@ -169,407 +167,360 @@ impl Debug for Turn {
     }
 }
 
-#[derive(Debug)]
-struct CycleVec<T> {
-    _vec: Vec<T>,
+struct Grid<T> {
+    _v: Vec<Vec<T>>,
 }
 
-impl<T> Deref for CycleVec<T> {
-    type Target = Vec<T>;
-    fn deref(&self) -> &Self::Target {
-        &self._vec
+impl<T> Debug for Grid<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.iter().try_for_each(|line| {
+            line.iter().try_for_each(|t| t.fmt(f))?;
+            f.write_char('\n')
+        })
     }
 }
 
-impl<T> DerefMut for CycleVec<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self._vec
-    }
-}
+impl Grid<Tile> {}
 
-impl<T> From<Vec<T>> for CycleVec<T> {
-    fn from(value: Vec<T>) -> Self {
-        Self { _vec: value }
+impl<T> Deref for Grid<T> {
+    type Target = Vec<Vec<T>>;
+    fn deref(&self) -> &Self::Target {
+        &self._v
     }
 }
 
-impl<T> CycleVec<T>
-where
-    T: TileTrait,
-{
-    fn get_non_void_ending_len(&self) -> usize {
-        self.iter()
-            .rposition(|t| !t.is_void())
-            .expect("Failed rposition")
-    }
-    fn get_first(&self) -> usize {
-        self.iter()
-            .position(|t| !t.is_void())
-            .expect("Failed finding valid tile")
-    }
-    fn go_left(&self, x: usize) -> usize {
-        match x.checked_sub(1) {
-            None => self.get_non_void_ending_len(),
-            Some(xx) if xx < self.get_first() => self.get_non_void_ending_len(),
-            Some(xx) => xx,
-        }
-    }
-    fn go_right(&self, x: usize) -> usize {
-        let xx = if x + 1 <= self.get_non_void_ending_len().min(self.len() - 1) {
-            x + 1
-        } else {
-            self.get_first()
-        };
-        return xx;
+impl<T> DerefMut for Grid<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self._v
     }
 }
 
-impl<T> CycleVec<CycleVec<T>>
-where
-    T: TileTrait + Copy + Debug,
-{
-    fn get_first(&self) -> (usize, usize) {
-        (
-            self.first()
-                .expect("Called first on empty CycleVec")
-                .get_first(),
-            0,
-        )
-    }
-    fn get_col(&self, x: usize) -> CycleVec<T> {
-        let v: Vec<T> = self
-            .iter()
-            .filter_map(|cv| {
-                if let Some(t) = cv.get(x) {
-                    Some(*t)
-                } else {
-                    None
-                }
-            })
-            .collect();
-        CycleVec::from(v)
-    }
-
-    fn go_left(&self, (x, y): (usize, usize)) -> (usize, usize) {
-        (self.get(y).expect("Wrong y index").go_left(x), y)
-    }
-    fn go_right(&self, (x, y): (usize, usize)) -> (usize, usize) {
-        (self.get(y).expect("Wrong y index").go_right(x), y)
-    }
-    fn go_up(&self, (x, y): (usize, usize)) -> (usize, usize) {
-        (x, self.get_col(x).go_left(y))
-    }
-    fn go_down(&self, (x, y): (usize, usize)) -> (usize, usize) {
-        (x, self.get_col(x).go_right(y))
-    }
+/// One square face, cut out of the flat net.
+struct Face {
+    grid: Grid<Tile>,
+    /// Top-left corner of the face on the flat net.
+    offset: (usize, usize),
 }
 
-struct Map {
-    map: CycleVec<CycleVec<Tile>>,
-    history: HashMap<(usize, usize), Direction>,
-    instructions: Vec<(u32, Option<Turn>)>,
-    p: (usize, usize),
+impl Face {
+    fn width(&self) -> usize {
+        self.grid.first().unwrap().len()
+    }
 }
 
-impl Map {
-    fn walk(&mut self, d: Direction) -> Result<(), ()> {
-        let np = match d {
-            Direction::D => self.map.go_down(self.p),
-            Direction::L => self.map.go_left(self.p),
-            Direction::R => self.map.go_right(self.p),
-            Direction::U => self.map.go_up(self.p),
+impl From<(&Grid<Tile>, usize, usize, usize)> for Face {
+    fn from((super_grid, x, y, width): (&Grid<Tile>, usize, usize, usize)) -> Self {
+        let grid = Grid {
+            _v: super_grid[y..(y + width)]
+                .iter()
+                .map(|line| line[x..(x + width)].to_vec())
+                .collect(),
         };
-        let nt = self.map[np.1][np.0];
-        assert!(!nt.is_void());
-        if nt.is_wall() {
-            return Err(());
+        assert!(
+            grid.iter().all(|line| line.iter().all(|t| !t.is_void())),
+            "Void tile in face"
+        );
+        Face {
+            grid,
+            offset: (x, y),
         }
-        self.history.insert(self.p, d);
-        self.p = np;
-        return Ok(());
     }
 }
 
-impl Debug for Map {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.map.iter().enumerate().try_for_each(|(y, c)| {
-            c.iter().enumerate().try_for_each(|(x, el)| {
-                if (x, y) == self.p {
-                    f.write_char('P')
-                } else {
-                    match self.history.get(&(x, y)) {
-                        Some(&dir) => dir.fmt(f),
-                        None => el.fmt(f),
-                    }
-                }
-            })?;
-            f.write_char('\n')?;
-            Ok(())
-        })?;
-        f.write_char('\n')
-        // self.instructions.iter().try_for_each(|(ic, i)| {
-        //     f.write_fmt(format_args!("{ic}"))?;
-        //     i.fmt(f)?;
-        //     Ok(())
-        // })?;
-        // f.write_char('\n')
-    }
-}
+const SIDES: [Direction; 4] = [Direction::U, Direction::R, Direction::D, Direction::L];
 
-impl From<&str> for Map {
-    fn from(value: &str) -> Self {
-        fn parse_tile(input: &str) -> IResult<&str, Tile> {
-            map(alt((tag(" "), tag("."), tag("#"))), Tile::from)(input)
-        }
-        fn parse_map_line(input: &str) -> IResult<&str, CycleVec<Tile>> {
-            map(many0(parse_tile), CycleVec::from)(input)
-        }
-        fn parse_map(input: &str) -> IResult<&str, CycleVec<CycleVec<Tile>>> {
-            map(
-                many1(terminated(parse_map_line, char('\n'))),
-                CycleVec::from,
-            )(input)
-        }
-        fn parse_instr(input: &str) -> IResult<&str, Vec<(u32, Option<Turn>)>> {
-            many1(map(tuple((digit1, alpha0)), |(digits, turn)| {
-                (
-                    u32::from_str_radix(digits, 10).expect("Error parsing instr"),
-                    match turn {
-                        "" => None,
-                        t => Some(Turn::try_from(t.chars().next().unwrap()).unwrap()),
-                    },
-                )
-            }))(input.trim())
-        }
-        let sep = value.find("\n\n").unwrap();
-        let split = value.split_at(sep + 1);
-        let mut _map = match parse_map(split.0) {
-            Ok((_, mut _map)) => _map,
-            Err(_err) => {
-                panic!("Failed parsing Map")
-            }
-        };
-        let instr = parse_instr(split.1)
-            .expect("Failed to parse instructions")
-            .1;
-        let p = _map.get_first();
-        Map {
-            instructions: instr,
-            history: HashMap::new(),
-            map: _map,
-            p,
-        }
-    }
+fn side_index(side: Direction) -> usize {
+    SIDES.iter().position(|&s| s == side).unwrap()
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let mut map: Map = Map::from(input);
-    let mut dir = Direction::R;
-    let instrs = map.instructions.clone();
-    for (nd, turn) in instrs {
-        for _ in 0..nd {
-            match map.walk(dir) {
-                Err(()) => {
-                    // Hit a wall
-                    break;
-                }
-                Ok(()) => (),
-            }
-        }
-        if let Some(t) = turn {
-            dir += t;
-        }
+/// Position along a side of a face: `x` for the `U` and `D` sides, `y` for `L` and `R`.
+fn along_side(side: Direction, x: usize, y: usize) -> usize {
+    match side {
+        Direction::U | Direction::D => x,
+        Direction::L | Direction::R => y,
     }
-    let dir_v: usize = dir.into();
-    Some(1000 * (map.p.1 + 1) + 4 * (map.p.0 + 1) + dir_v)
 }
 
-struct Grid<T> {
-    _v: Vec<Vec<T>>,
+/// Where the walker lands when it leaves a face through one of its sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edge {
+    face: usize,
+    /// Side of `face` through which it is entered.
+    entry: Direction,
+    /// Whether positions along the side are reversed.
+    flip: bool,
 }
 
-impl<T> Debug for Grid<T>
-where
-    T: Debug,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.iter().try_for_each(|line| {
-            line.iter().try_for_each(|t| t.fmt(f))?;
-            f.write_char('\n')
-        })
+impl Edge {
+    /// Coordinates and direction on `self.face` after leaving a face of
+    /// width `width` from `(x, y)` going `exit`.
+    fn cross(
+        &self,
+        exit: Direction,
+        x: usize,
+        y: usize,
+        width: usize,
+    ) -> (usize, usize, Direction) {
+        let t = along_side(exit, x, y);
+        let t = if self.flip { width - 1 - t } else { t };
+        let (x, y) = match self.entry {
+            Direction::U => (t, 0),
+            Direction::R => (width - 1, t),
+            Direction::D => (t, width - 1),
+            Direction::L => (0, t),
+        };
+        (x, y, self.entry.oppose())
     }
 }
 
-impl Grid<Tile> {}
+/// How the sides of the faces of a net are wired together.
+trait WrapRule {
+    fn from_net(net: &Net) -> Self;
+    fn edge(&self, face: usize, exit: Direction) -> Edge;
+}
 
-impl<T> Deref for Grid<T> {
-    type Target = Vec<Vec<T>>;
-    fn deref(&self) -> &Self::Target {
-        &self._v
-    }
+/// The six square faces found on a flat net, in reading order.
+#[derive(Debug)]
+struct Net {
+    width: usize,
+    /// Position of each face on the net, in face widths.
+    cells: Vec<(usize, usize)>,
 }
 
-impl<T> DerefMut for Grid<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self._v
+impl Net {
+    fn parse(flat: &[Vec<Tile>]) -> Self {
+        let n_tiles = flat
+            .iter()
+            .map(|line| line.iter().filter(|t| !t.is_void()).count())
+            .sum::<usize>();
+        let width = ((n_tiles / 6) as f64).sqrt() as usize;
+        assert_eq!(width * width * 6, n_tiles, "Not a cube net");
+        let is_face = |(cx, cy): (usize, usize)| {
+            flat.get(cy * width)
+                .and_then(|line| line.get(cx * width))
+                .is_some_and(|t| !t.is_void())
+        };
+        let n_cols = flat.iter().map(Vec::len).max().unwrap_or(0) / width;
+        let cells: Vec<(usize, usize)> = (0..flat.len() / width)
+            .flat_map(|cy| (0..n_cols).map(move |cx| (cx, cy)))
+            .filter(|&cell| is_face(cell))
+            .collect();
+        assert_eq!(cells.len(), 6, "Not a cube net");
+        Net { width, cells }
+    }
+    fn offset(&self, face: usize) -> (usize, usize) {
+        let (cx, cy) = self.cells[face];
+        (cx * self.width, cy * self.width)
+    }
+    /// Face next to `face` on the net, on its `dir` side.
+    fn neighbour(&self, face: usize, dir: Direction) -> Option<usize> {
+        let (cx, cy) = self.cells[face];
+        let cell = match dir {
+            Direction::U => cy.checked_sub(1).map(|cy| (cx, cy)),
+            Direction::R => Some((cx + 1, cy)),
+            Direction::D => Some((cx, cy + 1)),
+            Direction::L => cx.checked_sub(1).map(|cx| (cx, cy)),
+        }?;
+        self.cells.iter().position(|&c| c == cell)
+    }
+}
+
+/// Part one: leaving a face wraps around to the other end of its row or column.
+struct FlatWrap {
+    edges: Vec<[Edge; 4]>,
+}
+
+impl WrapRule for FlatWrap {
+    fn from_net(net: &Net) -> Self {
+        let edges = (0..net.cells.len())
+            .map(|face| {
+                SIDES.map(|exit| {
+                    let mut next = face;
+                    match net.neighbour(face, exit) {
+                        Some(neigh) => next = neigh,
+                        None => {
+                            while let Some(prev) = net.neighbour(next, exit.oppose()) {
+                                next = prev;
+                            }
+                        }
+                    }
+                    Edge {
+                        face: next,
+                        entry: exit.oppose(),
+                        flip: false,
+                    }
+                })
+            })
+            .collect();
+        FlatWrap { edges }
+    }
+    fn edge(&self, face: usize, exit: Direction) -> Edge {
+        self.edges[face][side_index(exit)]
     }
 }
 
-struct CubeFace {
-    grid: Grid<Tile>,
-    up: Option<(Weak<RefCell<Self>>, Direction)>,
-    right: Option<(Weak<RefCell<Self>>, Direction)>,
-    down: Option<(Weak<RefCell<Self>>, Direction)>,
-    left: Option<(Weak<RefCell<Self>>, Direction)>,
+type Vec3 = [i8; 3];
+
+fn neg(v: Vec3) -> Vec3 {
+    [-v[0], -v[1], -v[2]]
 }
 
-impl CubeFace {
-    fn width(&self) -> usize {
-        self.grid.first().unwrap().len()
-    }
-    fn set_neigh(
-        &mut self,
-        up: (Weak<RefCell<Self>>, Direction),
-        right: (Weak<RefCell<Self>>, Direction),
-        down: (Weak<RefCell<Self>>, Direction),
-        left: (Weak<RefCell<Self>>, Direction),
-    ) {
-        self.up = Some(up);
-        self.right = Some(right);
-        self.down = Some(down);
-        self.left = Some(left);
-    }
+/// Where the right, down and outward directions of a face point once the net is folded.
+#[derive(Debug, Clone, Copy)]
+struct FaceOrientation {
+    right: Vec3,
+    down: Vec3,
+    normal: Vec3,
+}
 
-    fn go_dir(
-        &self,
-        (weak_self, x, y, dir): &(Weak<RefCell<Self>>, usize, usize, Direction),
-    ) -> (Weak<RefCell<Self>>, usize, usize, Direction) {
+impl FaceOrientation {
+    /// Outward direction of one side of the face.
+    fn side(&self, dir: Direction) -> Vec3 {
         match dir {
-            Direction::U => match y.checked_sub(1) {
-                Some(new_y) => (weak_self.clone(), *x, new_y, *dir),
-                None => {
-                    let (next_up, incoming_dir) = self.up.as_ref().unwrap();
-                    let new_coords = translate_coords(dir, incoming_dir, *x, *y, self.width());
-                    (
-                        next_up.clone(),
-                        new_coords.0,
-                        new_coords.1,
-                        incoming_dir.oppose(),
-                    )
-                }
+            Direction::U => neg(self.down),
+            Direction::R => self.right,
+            Direction::D => self.down,
+            Direction::L => neg(self.right),
+        }
+    }
+    /// Orientation of the face next to this one in the net, folded along the `dir` side.
+    fn fold(&self, dir: Direction) -> Self {
+        let FaceOrientation {
+            right,
+            down,
+            normal,
+        } = *self;
+        match dir {
+            Direction::U => FaceOrientation {
+                right,
+                down: normal,
+                normal: neg(down),
             },
-            Direction::R => {
-                if x + 1 < self.width() {
-                    (weak_self.clone(), x + 1, *y, *dir)
-                } else {
-                    let (next_right, incoming_dir) = self.right.as_ref().unwrap();
-                    let new_coords = translate_coords(dir, incoming_dir, *x, *y, self.width());
-                    (
-                        next_right.clone(),
-                        new_coords.0,
-                        new_coords.1,
-                        incoming_dir.oppose(),
-                    )
-                }
-            }
-            Direction::D => {
-                if y + 1 < self.width() {
-                    (weak_self.clone(), *x, y + 1, *dir)
-                } else {
-                    let (next_down, incoming_dir) = self.down.as_ref().unwrap();
-                    let new_coords = translate_coords(dir, incoming_dir, *x, *y, self.width());
-                    (
-                        next_down.clone(),
-                        new_coords.0,
-                        new_coords.1,
-                        incoming_dir.oppose(),
-                    )
-                }
-            }
-            Direction::L => match x.checked_sub(1) {
-                Some(new_x) => (weak_self.clone(), new_x, *y, *dir),
-                None => {
-                    let (next_left, incoming_dir) = self.left.as_ref().unwrap();
-                    let new_coords = translate_coords(dir, incoming_dir, *x, *y, self.width());
-                    (
-                        next_left.clone(),
-                        new_coords.0,
-                        new_coords.1,
-                        incoming_dir.oppose(),
-                    )
-                }
+            Direction::R => FaceOrientation {
+                right: neg(normal),
+                down,
+                normal: right,
+            },
+            Direction::D => FaceOrientation {
+                right,
+                down: neg(normal),
+                normal: down,
+            },
+            Direction::L => FaceOrientation {
+                right: normal,
+                down,
+                normal: neg(right),
             },
         }
     }
 }
 
-fn translate_coords(
-    moving_dir: &Direction,
-    incoming_dir: &Direction,
-    x: usize,
-    y: usize,
-    width: usize,
-) -> (usize, usize) {
-    let width_max_indx = width - 1;
-    // ugly...
-    match (moving_dir, incoming_dir) {
-        (Direction::D, Direction::R) => (width_max_indx, x),
-        (Direction::R, Direction::D) => (y, width_max_indx),
-
-        // opposing v
-        (Direction::D, Direction::U) => (x, 0),
-        (Direction::U, Direction::D) => (x, width_max_indx),
-        // opposing h
-        (Direction::R, Direction::L) => (0, y),
-        (Direction::L, Direction::R) => (width_max_indx, y),
-
-        (Direction::L, Direction::U) => (y, 0),
-        (Direction::U, Direction::L) => (0, x),
-
-        (Direction::L, Direction::L) => (0, width_max_indx - y),
-        (Direction::R, Direction::R) => (width_max_indx, width_max_indx - y),
-
-        (_, _) => {
-            println!("{moving_dir:?}{incoming_dir:?}");
-            unreachable!();
-        }
-    }
+/// Part two: faces are wired as if the net was folded into a cube.
+struct CubeWrap {
+    edges: Vec<[Edge; 4]>,
 }
 
-impl From<(&Grid<Tile>, usize, usize, usize)> for CubeFace {
-    fn from((super_grid, x, y, width): (&Grid<Tile>, usize, usize, usize)) -> Self {
-        let mut grid: Grid<Tile> = Grid { _v: Vec::new() };
-        // println!("width: {width}");
-        super_grid[y..(y + width)].iter().for_each(|line| {
-            grid._v
-                .push(line[x..(x + width)].iter().map(|&t| t).collect())
+impl WrapRule for CubeWrap {
+    fn from_net(net: &Net) -> Self {
+        // walk the net from the first face, folding along each shared side
+        let mut orientations: Vec<Option<FaceOrientation>> = vec![None; net.cells.len()];
+        orientations[0] = Some(FaceOrientation {
+            right: [1, 0, 0],
+            down: [0, 1, 0],
+            normal: [0, 0, 1],
         });
+        let mut to_visit = vec![0];
+        while let Some(face) = to_visit.pop() {
+            let orientation = orientations[face].unwrap();
+            for dir in SIDES {
+                if let Some(neigh) = net.neighbour(face, dir) {
+                    if orientations[neigh].is_none() {
+                        orientations[neigh] = Some(orientation.fold(dir));
+                        to_visit.push(neigh);
+                    }
+                }
+            }
+        }
+        let orientations: Vec<FaceOrientation> = orientations
+            .into_iter()
+            .map(|o| o.expect("Net faces are not connected"))
+            .collect();
 
-        // asset no void tile in grid
-        // println!("{grid:?}");
-        assert!(grid
+        // `D` and `L` sides go against the clockwise order around a face, and a shared
+        // side goes clockwise on one face and counter-clockwise on the other
+        let against_clockwise = |side| matches!(side, Direction::D | Direction::L);
+        let edges = orientations
             .iter()
-            .find(|line| line.iter().find(|t| t.is_void()).is_some())
-            .is_none());
-
-        CubeFace {
-            grid,
-            up: None,
-            right: None,
-            down: None,
-            left: None,
-        }
+            .map(|orientation| {
+                SIDES.map(|exit| {
+                    let face = orientations
+                        .iter()
+                        .position(|o| o.normal == orientation.side(exit))
+                        .expect("Net does not fold into a cube");
+                    let entry = SIDES
+                        .into_iter()
+                        .find(|&side| orientations[face].side(side) == orientation.normal)
+                        .unwrap();
+                    Edge {
+                        face,
+                        entry,
+                        flip: against_clockwise(exit) == against_clockwise(entry),
+                    }
+                })
+            })
+            .collect();
+        CubeWrap { edges }
     }
+    fn edge(&self, face: usize, exit: Direction) -> Edge {
+        self.edges[face][side_index(exit)]
+    }
+}
+
+/// Move from one face to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EdgeCrossing {
+    from_face: usize,
+    /// Direction the walker was going when leaving `from_face`.
+    exit: Direction,
+    to_face: usize,
+    /// Direction the walker is going once on `to_face`.
+    entry: Direction,
+}
+
+/// Position on the flat net after a move.
+#[derive(Debug, Clone, Copy)]
+struct HistoryEntry {
+    x: usize,
+    y: usize,
+    dir: Direction,
+    face: usize,
+    crossing: Option<EdgeCrossing>,
+}
+
+/// Position of the walker on a face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    face: usize,
+    x: usize,
+    y: usize,
+    dir: Direction,
 }
 
-struct Cube {
-    faces: [Rc<RefCell<CubeFace>>; 6],
+/// The map folded according to a `WrapRule`, with the walker on it.
+struct Board<W> {
+    faces: Vec<Face>,
+    wrap: W,
+    /// Width and height of the flat net.
+    net_size: (usize, usize),
     instr: Vec<(u32, Option<Turn>)>,
-    pos: (Weak<RefCell<CubeFace>>, usize, usize, Direction),
-    history: Vec<(usize, usize, Direction)>,
+    pos: Position,
+    history: Vec<HistoryEntry>,
 }
 
-impl From<&str> for Cube {
+impl<W: WrapRule> From<&str> for Board<W> {
     fn from(value: &str) -> Self {
         fn parse_tile(input: &str) -> IResult<&str, Tile> {
             map(alt((tag(" "), tag("."), tag("#"))), Tile::from)(input)
@@ -577,108 +528,73 @@ impl From<&str> for Cube {
         fn parse_line(input: &str) -> IResult<&str, Vec<Tile>> {
             many0(parse_tile)(input)
         }
-        fn parse_flat_cube(input: &str) -> IResult<&str, Vec<Vec<Tile>>> {
+        fn parse_flat_map(input: &str) -> IResult<&str, Vec<Vec<Tile>>> {
             many1(terminated(parse_line, char('\n')))(input)
         }
         fn parse_instr(input: &str) -> IResult<&str, Vec<(u32, Option<Turn>)>> {
-            many1(map(tuple((digit1, alpha0)), |(digits, turn)| {
-                (
-                    u32::from_str_radix(digits, 10).expect("Error parsing instr"),
-                    match turn {
-                        "" => None,
-                        t => Some(Turn::try_from(t.chars().next().unwrap()).unwrap()),
-                    },
-                )
-            }))(input.trim())
+            many1(map(
+                tuple((digit1, alpha0)),
+                |(digits, turn): (&str, &str)| {
+                    (
+                        digits.parse().expect("Error parsing instr"),
+                        match turn {
+                            "" => None,
+                            t => Some(Turn::try_from(t.chars().next().unwrap()).unwrap()),
+                        },
+                    )
+                },
+            ))(input.trim())
         }
         let sep = value.find("\n\n").unwrap();
         let split = value.split_at(sep + 1);
-        let flat_cube = match parse_flat_cube(split.0) {
-            Ok((_, mut _map)) => _map,
+        let flat_map = match parse_flat_map(split.0) {
+            Ok((_, _map)) => _map,
             Err(_err) => {
                 panic!("Failed parsing Map")
             }
         };
 
-        let cube_width = flat_cube
-            .first()
-            .expect("flat_cube shouldn't be empty vec")
-            .iter()
-            .position(|t| !t.is_void())
-            .expect("Failed finding valid tile on first line");
-        let super_grid = Grid { _v: flat_cube };
-
-        /*
-         *      12
-         *      3
-         *     45
-         *     6
-         */
-        let [c0, c1, c2, c3, c4, c5] = (0..6)
-            .map(|n| {
-                Rc::new(RefCell::new(CubeFace::from((
-                    &super_grid,
-                    get_face_offset(n, cube_width).0,
-                    get_face_offset(n, cube_width).1,
-                    cube_width,
-                ))))
-            })
-            .array_chunks::<6>()
-            .next()
-            .unwrap();
-
-        c0.borrow_mut().set_neigh(
-            (Rc::downgrade(&c5), Direction::L),
-            (Rc::downgrade(&c1), Direction::L),
-            (Rc::downgrade(&c2), Direction::U),
-            (Rc::downgrade(&c3), Direction::L),
-        );
-        c1.borrow_mut().set_neigh(
-            (Rc::downgrade(&c5), Direction::D),
-            (Rc::downgrade(&c4), Direction::R),
-            (Rc::downgrade(&c2), Direction::R),
-            (Rc::downgrade(&c0), Direction::R),
-        );
-        c2.borrow_mut().set_neigh(
-            (Rc::downgrade(&c0), Direction::D),
-            (Rc::downgrade(&c1), Direction::D),
-            (Rc::downgrade(&c4), Direction::U),
-            (Rc::downgrade(&c3), Direction::U),
-        );
-        c3.borrow_mut().set_neigh(
-            (Rc::downgrade(&c2), Direction::L),
-            (Rc::downgrade(&c4), Direction::L),
-            (Rc::downgrade(&c5), Direction::U),
-            (Rc::downgrade(&c0), Direction::L),
-        );
-        c4.borrow_mut().set_neigh(
-            (Rc::downgrade(&c2), Direction::D),
-            (Rc::downgrade(&c1), Direction::R),
-            (Rc::downgrade(&c5), Direction::R),
-            (Rc::downgrade(&c3), Direction::R),
-        );
-        c5.borrow_mut().set_neigh(
-            (Rc::downgrade(&c3), Direction::D),
-            (Rc::downgrade(&c4), Direction::D),
-            (Rc::downgrade(&c1), Direction::U),
-            (Rc::downgrade(&c0), Direction::U),
+        let net = Net::parse(&flat_map);
+        let net_size = (
+            flat_map.iter().map(Vec::len).max().unwrap_or(0),
+            flat_map.len(),
         );
+        let super_grid = Grid { _v: flat_map };
+        let faces = (0..net.cells.len())
+            .map(|face| {
+                let (x, y) = net.offset(face);
+                Face::from((&super_grid, x, y, net.width))
+            })
+            .collect();
         let instr = parse_instr(split.1)
             .expect("Failed to parse instructions")
             .1;
-        let pos = (Rc::downgrade(&c0), 0, 0, Direction::R);
-        Cube {
-            faces: [c0, c1, c2, c3, c4, c5],
+        let pos = Position {
+            face: 0,
+            x: 0,
+            y: 0,
+            dir: Direction::R,
+        };
+        Board {
+            faces,
+            wrap: W::from_net(&net),
+            net_size,
             pos,
             instr,
-            history: vec![(cube_width, 0, Direction::R)],
+            history: vec![HistoryEntry {
+                x: net.offset(0).0,
+                y: 0,
+                dir: Direction::R,
+                face: 0,
+                crossing: None,
+            }],
         }
     }
 }
 
-impl Into<char> for &Tile {
-    fn into(self) -> char {
-        match self {
+impl From<&Tile> for char {
+    fn from(tile: &Tile) -> Self {
+        match tile {
             Tile::Floor => '.',
             Tile::Wall => '#',
             Tile::Void => ' ',
@@ -686,9 +602,9 @@ impl Into<char> for &Tile {
     }
 }
 
-impl Into<char> for &Direction {
-    fn into(self) -> char {
-        match self {
+impl From<&Direction> for char {
+    fn from(dir: &Direction) -> Self {
+        match dir {
             Direction::U => '^',
             Direction::R => '>',
             Direction::D => 'v',
@@ -697,21 +613,13 @@ impl Into<char> for &Direction {
     }
 }
 
-impl Debug for Cube {
+impl<W> Debug for Board<W> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let width = self.faces[0].try_borrow().unwrap().width();
-        let mut lines = Vec::with_capacity(width * 4);
-        let mut tpl_line = Vec::with_capacity(width * 4);
-        for _ in 0..(width * 4) {
-            tpl_line.push(' ');
-        }
-        for _ in 0..(width * 4) {
-            lines.push(tpl_line.clone());
-        }
-        for (index, cube) in self.faces.iter().enumerate() {
-            let (off_x, off_y) = get_face_offset(index, width);
-            let cube = cube.try_borrow().unwrap();
-            cube.grid.iter().enumerate().for_each(|(y, line)| {
+        let (net_width, net_height) = self.net_size;
+        let mut lines = vec![vec![' '; net_width]; net_height];
+        for face in &self.faces {
+            let (off_x, off_y) = face.offset;
+            face.grid.iter().enumerate().for_each(|(y, line)| {
                 line.iter().enumerate().for_each(|(x, t)| {
                     lines[off_y + y][off_x + x] = t.into();
                 });
@@ -719,7 +627,7 @@ impl Debug for Cube {
         }
         self.history
             .iter()
-            .for_each(|(rx, ry, dir)| lines[*ry][*rx] = dir.into());
+            .for_each(|h| lines[h.y][h.x] = (&h.dir).into());
         lines.iter().try_for_each(|line| {
             line.iter().try_for_each(|&c| f.write_char(c))?;
             f.write_char('\n')
@@ -727,110 +635,227 @@ impl Debug for Cube {
     }
 }
 
-fn get_face_offset(n: usize, width: usize) -> (usize, usize) {
-    if n == 0 {
-        return (width, 0);
-    } else if n == 1 {
-        return (2 * width, 0);
-    } else if n == 2 {
-        return (width, width);
-    } else if n == 3 {
-        return (0, 2 * width);
-    } else if n == 4 {
-        return (width, 2 * width);
-    } else if n == 5 {
-        return (0, 3 * width);
+impl<W: WrapRule> Board<W> {
+    /// Next position going forward, and the edge crossed to get there if any.
+    fn next_pos(&self) -> (Position, Option<EdgeCrossing>) {
+        let Position { face, x, y, dir } = self.pos;
+        let width = self.faces[face].width();
+        let inside = match dir {
+            Direction::U => y.checked_sub(1).map(|y| (x, y)),
+            Direction::R => (x + 1 < width).then_some((x + 1, y)),
+            Direction::D => (y + 1 < width).then_some((x, y + 1)),
+            Direction::L => x.checked_sub(1).map(|x| (x, y)),
+        };
+        match inside {
+            Some((x, y)) => (Position { face, x, y, dir }, None),
+            None => {
+                let edge = self.wrap.edge(face, dir);
+                let (x, y, new_dir) = edge.cross(dir, x, y, width);
+                let crossing = EdgeCrossing {
+                    from_face: face,
+                    exit: dir,
+                    to_face: edge.face,
+                    entry: new_dir,
+                };
+                let pos = Position {
+                    face: edge.face,
+                    x,
+                    y,
+                    dir: new_dir,
+                };
+                (pos, Some(crossing))
+            }
+        }
     }
-    unreachable!();
-}
 
-impl Cube {
     fn walk(&mut self) -> Result<(), ()> {
-        let np = self
-            .pos
-            .0
-            .upgrade()
-            .unwrap()
-            .try_borrow()
-            .unwrap()
-            .go_dir(&self.pos);
-        let (nx, ny) = (np.1, np.2);
-        let tile = np.0.upgrade().unwrap().try_borrow().unwrap().grid[ny][nx];
+        let (np, crossing) = self.next_pos();
+        let tile = self.faces[np.face].grid[np.y][np.x];
         assert!(!tile.is_void());
         if tile.is_wall() {
             return Err(());
-        } else if tile.is_void() {
-            panic!("In void");
-        } else {
-            self.pos = np;
-            let real_coords = self.get_real_coords();
-            self.history
-                .push((real_coords.0, real_coords.1, self.pos.3))
         }
+        self.pos = np;
+        let real_coords = self.get_real_coords();
+        self.history.push(HistoryEntry {
+            x: real_coords.0,
+            y: real_coords.1,
+            dir: np.dir,
+            face: np.face,
+            crossing,
+        });
         Ok(())
     }
+}
 
-    fn print_real_cords(&self) {
-        let real_coords = self.get_real_coords();
-        println!(
-            "c={} r={}, d={}",
-            real_coords.0,
-            real_coords.1,
-            Into::<usize>::into(self.pos.3)
-        );
-    }
+impl<W> Board<W> {
     fn turn(&mut self, turn: Turn) {
-        self.pos = (
-            self.pos.0.clone(),
-            self.pos.1,
-            self.pos.2,
-            self.pos.3 + turn,
-        );
-    }
-    fn get_face_index(&self, face_ref: Weak<RefCell<CubeFace>>) -> usize {
-        (0..6)
-            .find(|&n| Rc::ptr_eq(&face_ref.upgrade().unwrap(), &self.faces[n]))
-            .unwrap()
+        self.pos.dir += turn;
     }
     fn get_real_coords(&self) -> (usize, usize) {
-        let coords = (self.pos.1, self.pos.2);
-        let width = self.faces[0].try_borrow().unwrap().width();
+        let (offset_x, offset_y) = self.faces[self.pos.face].offset;
+        (self.pos.x + offset_x, self.pos.y + offset_y)
+    }
+}
 
-        let face_ref = self.pos.0.clone();
-        let (offset_x, offset_y) = get_face_offset(self.get_face_index(face_ref), width);
+/// Steps forward and backward through the history of a walk on the board.
+struct Replay<'a, W> {
+    board: &'a Board<W>,
+    step: usize,
+}
 
-        (coords.0 + offset_x, coords.1 + offset_y)
+impl<'a, W> Replay<'a, W> {
+    fn new(board: &'a Board<W>) -> Self {
+        Replay { board, step: 0 }
+    }
+    fn forward(&mut self, n: usize) {
+        self.step = (self.step + n).min(self.board.history.len() - 1);
+    }
+    fn backward(&mut self, n: usize) {
+        self.step = self.step.saturating_sub(n);
+    }
+    fn status(&self) -> String {
+        let entry = &self.board.history[self.step];
+        let mut status = format!(
+            "step {}/{} - face {} - x={} y={} facing {:?}",
+            self.step,
+            self.board.history.len() - 1,
+            entry.face + 1,
+            entry.x,
+            entry.y,
+            entry.dir
+        );
+        if let Some(c) = entry.crossing {
+            status.push_str(&format!(
+                "\nedge: face {} going {:?} -> face {} going {:?}",
+                c.from_face + 1,
+                c.exit,
+                c.to_face + 1,
+                c.entry
+            ));
+        }
+        status
+    }
+    /// The flat net with the path walked so far; tiles of the current face are bold.
+    fn render(&self) -> String {
+        let (net_width, net_height) = self.board.net_size;
+        let mut lines = vec![vec![' '; net_width]; net_height];
+        let mut face_of = vec![vec![None; net_width]; net_height];
+        for (index, face) in self.board.faces.iter().enumerate() {
+            let (off_x, off_y) = face.offset;
+            face.grid.iter().enumerate().for_each(|(y, line)| {
+                line.iter().enumerate().for_each(|(x, t)| {
+                    lines[off_y + y][off_x + x] = t.into();
+                    face_of[off_y + y][off_x + x] = Some(index);
+                });
+            });
+        }
+        self.board.history[..self.step]
+            .iter()
+            .for_each(|h| lines[h.y][h.x] = (&h.dir).into());
+        let current = &self.board.history[self.step];
+        lines[current.y][current.x] = '@';
+
+        let mut frame = String::new();
+        for (line, faces) in lines.iter().zip(face_of) {
+            let mut bold = false;
+            for (&c, face) in line.iter().zip(faces) {
+                let on_face = face == Some(current.face);
+                if on_face != bold {
+                    frame.push_str(if on_face { ANSI_BOLD } else { ANSI_RESET });
+                    bold = on_face;
+                }
+                frame.push(c);
+            }
+            if bold {
+                frame.push_str(ANSI_RESET);
+            }
+            frame.push('\n');
+        }
+        frame.push_str(&self.status());
+        frame
+    }
+    /// Reads commands from stdin: `n [k]` / enter to go forward, `b [k]` to go back,
+    /// `e` to jump to the next edge crossing, `q` to quit.
+    fn run(&mut self) {
+        let stdin = io::stdin();
+        loop {
+            println!("{ANSI_CLEAR}{}", self.render());
+            print!("[n]ext [b]ack [e]dge [q]uit > ");
+            io::stdout().flush().unwrap();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap() == 0 {
+                return;
+            }
+            let mut words = line.split_whitespace();
+            let command = words.next().unwrap_or("n");
+            let n = words.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+            match command {
+                "n" => self.forward(n),
+                "b" => self.backward(n),
+                "e" => {
+                    let next_edge = self.board.history[self.step + 1..]
+                        .iter()
+                        .position(|h| h.crossing.is_some());
+                    self.forward(next_edge.map_or(usize::MAX, |i| i + 1));
+                }
+                "q" => return,
+                _ => {}
+            }
+        }
     }
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
-    let mut cube = Cube::from(input);
-    let instrs = cube.instr.clone();
-
-    for (nd, turn) in instrs {
-        for _ in 0..nd {
-            match cube.walk() {
-                Err(()) => {
+impl<W: WrapRule> Board<W> {
+    /// Follows every instruction, stopping each move at the first wall.
+    fn follow_instructions(&mut self) {
+        let instrs = self.instr.clone();
+        for (nd, turn) in instrs {
+            for _ in 0..nd {
+                if self.walk().is_err() {
                     // Hit a wall
                     break;
                 }
-                Ok(()) => (),
             }
-        }
-        if let Some(t) = turn {
-            cube.turn(t);
+            if let Some(t) = turn {
+                self.turn(t);
+            }
         }
     }
-    let dir_v: usize = cube.pos.3.into();
-    let (x, y) = cube.get_real_coords();
-    // println!("value is 1000 * (y:{y} + 1) + 4 * (x:{x} + 1) + {dir_v}");
-    Some(1000 * (y + 1) + 4 * (x + 1) + dir_v)
+}
+
+impl<W> Board<W> {
+    fn password(&self) -> usize {
+        let (x, y) = self.get_real_coords();
+        let dir_v: usize = self.pos.dir.into();
+        1000 * (y + 1) + 4 * (x + 1) + dir_v
+    }
+}
+
+fn solve<W: WrapRule>(input: &str) -> usize {
+    let mut board: Board<W> = Board::from(input);
+    board.follow_instructions();
+    board.password()
+}
+
+pub fn part_one(input: &str) -> Option<usize> {
+    Some(solve::<FlatWrap>(input))
+}
+
+pub fn part_two(input: &str) -> Option<usize> {
+    Some(solve::<CubeWrap>(input))
 }
 
 fn main() {
+    let mut args = pico_args::Arguments::from_env();
     let input = &advent_of_code::read_file("inputs", 22);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+    if args.contains("--replay") {
+        let mut board: Board<CubeWrap> = Board::from(input.as_str());
+        board.follow_instructions();
+        Replay::new(&board).run();
+    }
 }
 
 #[cfg(test)]
@@ -843,10 +868,127 @@ mod tests {
         assert_eq!(part_one(&input), Some(6032));
     }
 
+    #[test]
+    fn test_replay() {
+        let input = advent_of_code::read_file("inputs", 22);
+        let mut board: Board<CubeWrap> = Board::from(input.as_str());
+        board.follow_instructions();
+        let mut replay = Replay::new(&board);
+        assert!(replay.status().starts_with("step 0/"));
+        assert!(replay.render().contains('@'));
+        let first_edge = board
+            .history
+            .iter()
+            .position(|h| h.crossing.is_some())
+            .unwrap();
+        replay.forward(first_edge);
+        let crossing = board.history[first_edge].crossing.unwrap();
+        assert_ne!(crossing.from_face, crossing.to_face);
+        assert!(replay.status().contains("edge: face"));
+        replay.backward(1);
+        assert!(!replay.status().contains("edge: face"));
+    }
+
+    #[test]
+    fn test_fold_nets() {
+        // the 11 cube nets, as rows of face cells
+        let nets = [
+            "#...\n####\n#...",
+            "#...\n####\n.#..",
+            "#...\n####\n..#.",
+            "#...\n####\n...#",
+            ".#..\n####\n.#..",
+            ".#..\n####\n..#.",
+            "##..\n.###\n.#..",
+            "##..\n.###\n..#.",
+            "##..\n.###\n...#",
+            "##..\n.##.\n..##",
+            "###..\n..###",
+        ];
+        for net in nets {
+            let flat: Vec<Vec<Tile>> = net
+                .lines()
+                .flat_map(|row| {
+                    let line: Vec<Tile> = row
+                        .chars()
+                        .flat_map(|c| [if c == '#' { Tile::Floor } else { Tile::Void }; 2])
+                        .collect();
+                    [line.clone(), line]
+                })
+                .collect();
+            let net = Net::parse(&flat);
+            assert_eq!(net.width, 2);
+            let wrap = CubeWrap::from_net(&net);
+            for face in 0..6 {
+                let mut neighs: Vec<usize> = SIDES
+                    .iter()
+                    .map(|&side| wrap.edge(face, side).face)
+                    .collect();
+                for side in SIDES {
+                    let edge = wrap.edge(face, side);
+                    assert_ne!(edge.face, face);
+                    // going back through the entry side leads back through the same side
+                    let back = wrap.edge(edge.face, edge.entry);
+                    assert_eq!((back.face, back.entry, back.flip), (face, side, edge.flip));
+                }
+                neighs.sort();
+                neighs.dedup();
+                assert_eq!(neighs.len(), 4);
+            }
+        }
+    }
+
+    fn example_net() -> Net {
+        let input = advent_of_code::read_file("examples", 22);
+        let flat: Vec<Vec<Tile>> = input
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|c| Tile::from(&c.to_string()[..]))
+                    .collect()
+            })
+            .collect();
+        Net::parse(&flat)
+    }
+
+    #[test]
+    fn test_cube_edges() {
+        let wrap = CubeWrap::from_net(&example_net());
+        let edge = |face, exit| wrap.edge(face, exit);
+        let expect = |face, entry, flip| Edge { face, entry, flip };
+        assert_eq!(edge(3, Direction::R), expect(5, Direction::U, true));
+        assert_eq!(edge(4, Direction::D), expect(1, Direction::D, true));
+        assert_eq!(edge(2, Direction::U), expect(0, Direction::L, false));
+        // (11, 5) going right lands on (14, 8) going down
+        let (x, y, dir) = edge(3, Direction::R).cross(Direction::R, 3, 1, 4);
+        assert_eq!((x + 12, y + 8, dir), (14, 8, Direction::D));
+    }
+
+    #[test]
+    fn test_flat_edges() {
+        let wrap = FlatWrap::from_net(&example_net());
+        let edge = |face, exit| wrap.edge(face, exit);
+        let expect = |face, entry| Edge {
+            face,
+            entry,
+            flip: false,
+        };
+        assert_eq!(edge(0, Direction::R), expect(0, Direction::L));
+        assert_eq!(edge(0, Direction::U), expect(4, Direction::D));
+        assert_eq!(edge(1, Direction::L), expect(3, Direction::R));
+        assert_eq!(edge(5, Direction::U), expect(5, Direction::D));
+    }
+
+    #[test]
+    fn test_part_two_example() {
+        let input = advent_of_code::read_file("examples", 22);
+        assert_eq!(part_two(&input), Some(5031));
+    }
+
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("inputs", 22);
-        // let input = advent_of_code::read_file("examples", 22);
         assert_eq!(part_two(&input), Some(108311));
     }
 }
@@ -8,18 +8,24 @@ use std::{
     process,
 };
 
-const MODULE_TEMPLATE: &str = r###"pub fn part_one(input: &str) -> Option<usize> {
+const MODULE_TEMPLATE: &str = r###"use advent_of_code::Params;
+
+/// Keys of the params files, for values that differ between the example and the real input.
+const PARAMS: &[&str] = &[];
+
+pub fn part_one(input: &str, params: &Params) -> Option<usize> {
     None
 }
 
-pub fn part_two(input: &str) -> Option<usize> {
+pub fn part_two(input: &str, params: &Params) -> Option<usize> {
     None
 }
 
 fn main() {
     let input = &advent_of_code::read_file("inputs", DAY);
-    advent_of_code::solve!(1, part_one, input);
-    advent_of_code::solve!(2, part_two, input);
+    let params = &advent_of_code::read_params("inputs", DAY, PARAMS);
+    advent_of_code::solve!(1, part_one, input, params);
+    advent_of_code::solve!(2, part_two, input, params);
 }
 
 #[cfg(test)]
@@ -29,13 +35,15 @@ mod tests {
     #[test]
     fn test_part_one() {
         let input = advent_of_code::read_file("examples", DAY);
-        assert_eq!(part_one(&input), None);
+        let params = advent_of_code::read_params("examples", DAY, PARAMS);
+        assert_eq!(part_one(&input, &params), None);
     }
 
     #[test]
     fn test_part_two() {
         let input = advent_of_code::read_file("examples", DAY);
-        assert_eq!(part_two(&input), None);
+        let params = advent_of_code::read_params("examples", DAY, PARAMS);
+        assert_eq!(part_two(&input, &params), None);
     }
 }
 "###;
@@ -5,6 +5,21 @@
 
 use std::cmp::Ordering;
 
+pub mod bitset;
+pub mod cycle;
+pub mod graph;
+pub mod interval;
+pub mod ocr;
+pub mod parse;
+pub mod rng;
+pub mod topn;
+pub mod window;
+
+pub use bitset::{BitSet64, BitSetN, Interner};
+pub use interval::IntervalSet;
+pub use topn::TopN;
+pub use window::DistinctWindow;
+
 pub trait Within {
     fn within(&self, other1: &Self, other2: &Self) -> bool;
 }
@@ -85,6 +100,11 @@ where
         // println!("\tlen: {}", self.vec.len());
         self.vec[point.x() as usize + (point.y() as usize * self.line_l)] = val;
     }
+
+    pub fn get(&self, point: &Point) -> T {
+        let point = self.shift_coords(point);
+        self.vec[point.x() as usize + (point.y() as usize * self.line_l)]
+    }
 }
 
 #[macro_export]
@@ -111,4 +131,4 @@ macro_rules! btree_set {
             temp_set // Return the populated HashSet
         }
     };
-}
\ No newline at end of file
+}
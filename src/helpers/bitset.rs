@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// Set of indices in `0..64`, stored in a single `u64`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BitSet64(u64);
+
+impl BitSet64 {
+    pub const CAPACITY: usize = 64;
+
+    pub fn new() -> Self {
+        BitSet64(0)
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        BitSet64(bits)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if `idx` was not in the set yet.
+    pub fn insert(&mut self, idx: usize) -> bool {
+        assert!(idx < Self::CAPACITY, "BitSet64 index out of range: {idx}");
+        let was_absent = !self.contains(idx);
+        self.0 |= 1 << idx;
+        was_absent
+    }
+
+    /// Returns `true` if `idx` was in the set.
+    pub fn remove(&mut self, idx: usize) -> bool {
+        let was_present = self.contains(idx);
+        if was_present {
+            self.0 &= !(1 << idx);
+        }
+        was_present
+    }
+
+    /// Copy of the set with `idx` inserted.
+    pub fn with(mut self, idx: usize) -> Self {
+        self.insert(idx);
+        self
+    }
+
+    pub fn contains(&self, idx: usize) -> bool {
+        idx < Self::CAPACITY && self.0 & (1 << idx) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        BitSet64(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        BitSet64(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        BitSet64(self.0 & !other.0)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.0 & other.0 == 0
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let idx = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(idx)
+        })
+    }
+}
+
+impl FromIterator<usize> for BitSet64 {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet64::new();
+        iter.into_iter().for_each(|idx| {
+            set.insert(idx);
+        });
+        set
+    }
+}
+
+impl fmt::Debug for BitSet64 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Set of indices in `0..64 * W`, stored in `W` words.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BitSetN<const W: usize>([u64; W]);
+
+impl<const W: usize> Default for BitSetN<W> {
+    fn default() -> Self {
+        BitSetN([0; W])
+    }
+}
+
+impl<const W: usize> BitSetN<W> {
+    pub const CAPACITY: usize = 64 * W;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn words(&self) -> &[u64; W] {
+        &self.0
+    }
+
+    /// Returns `true` if `idx` was not in the set yet.
+    pub fn insert(&mut self, idx: usize) -> bool {
+        assert!(idx < Self::CAPACITY, "BitSetN index out of range: {idx}");
+        let was_absent = !self.contains(idx);
+        self.0[idx / 64] |= 1 << (idx % 64);
+        was_absent
+    }
+
+    /// Returns `true` if `idx` was in the set.
+    pub fn remove(&mut self, idx: usize) -> bool {
+        let was_present = self.contains(idx);
+        if was_present {
+            self.0[idx / 64] &= !(1 << (idx % 64));
+        }
+        was_present
+    }
+
+    /// Copy of the set with `idx` inserted.
+    pub fn with(mut self, idx: usize) -> Self {
+        self.insert(idx);
+        self
+    }
+
+    pub fn contains(&self, idx: usize) -> bool {
+        idx < Self::CAPACITY && self.0[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        let mut words = [0; W];
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = f(self.0[i], other.0[i]);
+        }
+        BitSetN(words)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(w, &word)| {
+            BitSet64::from_bits(word)
+                .iter()
+                .map(move |idx| w * 64 + idx)
+        })
+    }
+}
+
+impl<const W: usize> FromIterator<usize> for BitSetN<W> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSetN::new();
+        iter.into_iter().for_each(|idx| {
+            set.insert(idx);
+        });
+        set
+    }
+}
+
+impl<const W: usize> fmt::Debug for BitSetN<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Maps labels (e.g. `[char; 2]`) to dense bit indices, in insertion order.
+#[derive(Debug, Clone)]
+pub struct Interner<L> {
+    labels: Vec<L>,
+    indexes: HashMap<L, usize>,
+}
+
+impl<L> Default for Interner<L> {
+    fn default() -> Self {
+        Interner {
+            labels: Vec::new(),
+            indexes: HashMap::new(),
+        }
+    }
+}
+
+impl<L> Interner<L>
+where
+    L: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of `label`, assigning the next free one if needed.
+    pub fn intern(&mut self, label: L) -> usize {
+        if let Some(&idx) = self.indexes.get(&label) {
+            return idx;
+        }
+        let idx = self.labels.len();
+        self.labels.push(label.clone());
+        self.indexes.insert(label, idx);
+        idx
+    }
+
+    pub fn get(&self, label: &L) -> Option<usize> {
+        self.indexes.get(label).copied()
+    }
+
+    pub fn resolve(&self, idx: usize) -> &L {
+        &self.labels[idx]
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
+impl<L> FromIterator<L> for Interner<L>
+where
+    L: Clone + Eq + Hash,
+{
+    fn from_iter<I: IntoIterator<Item = L>>(iter: I) -> Self {
+        let mut interner = Interner::new();
+        iter.into_iter().for_each(|label| {
+            interner.intern(label);
+        });
+        interner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset64() {
+        let mut set = BitSet64::new();
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(63));
+        assert!(set.contains(3) && set.contains(63) && !set.contains(4));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![3, 63]);
+
+        let other: BitSet64 = [1, 3].into_iter().collect();
+        assert_eq!(set.union(&other).iter().collect::<Vec<_>>(), vec![1, 3, 63]);
+        assert_eq!(set.intersection(&other), BitSet64::new().with(3));
+        assert!(!set.is_disjoint(&other));
+        assert!(set.remove(3));
+        assert!(set.is_disjoint(&other));
+    }
+
+    #[test]
+    fn test_bitset_n() {
+        let mut set = BitSetN::<2>::new();
+        assert!(set.insert(0));
+        assert!(set.insert(64));
+        assert!(set.insert(127));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 64, 127]);
+        assert!(set.is_subset(&set.with(5)));
+        assert!(set.remove(64));
+        assert!(!set.contains(64));
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut interner: Interner<[char; 2]> = Interner::new();
+        assert_eq!(interner.intern(['A', 'A']), 0);
+        assert_eq!(interner.intern(['B', 'B']), 1);
+        assert_eq!(interner.intern(['A', 'A']), 0);
+        assert_eq!(interner.get(&['B', 'B']), Some(1));
+        assert_eq!(interner.get(&['C', 'C']), None);
+        assert_eq!(interner.resolve(1), &['B', 'B']);
+    }
+}
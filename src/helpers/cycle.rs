@@ -0,0 +1,285 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::hash::Hash;
+
+/// A cycle in a sequence of states: the state after `start + period` steps
+/// is the same as the state after `start` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Extrapolates `metric` after `target_steps`, knowing its values over the
+    /// first `start + period` steps (`metrics[i]` is the metric after `i` steps).
+    fn extrapolate(&self, metrics: &[usize], target_steps: usize) -> usize {
+        if target_steps < metrics.len() {
+            return metrics[target_steps];
+        }
+        let n_periods = (target_steps - self.start) / self.period;
+        let remainder = (target_steps - self.start) % self.period;
+        let per_period = metrics[self.start + self.period] - metrics[self.start];
+        metrics[self.start + remainder] + n_periods * per_period
+    }
+}
+
+/// Steps `state` until `target_steps` steps have been done or a state key repeats,
+/// then extrapolates `metric` after `target_steps` from the detected period.
+/// Every seen key is stored, see `skip_ahead_brent` / `skip_ahead_floyd` for large keys.
+pub fn skip_ahead<S, K>(
+    state: &mut S,
+    state_key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+    metric: impl Fn(&S) -> usize,
+    target_steps: usize,
+) -> usize
+where
+    K: Hash + Eq,
+{
+    let mut seen = HashMap::new();
+    let mut metrics = vec![metric(state)];
+    for i in 0..target_steps {
+        match seen.entry(state_key(state)) {
+            Entry::Occupied(entry) => {
+                let start = *entry.get();
+                let cycle = Cycle {
+                    start,
+                    period: i - start,
+                };
+                return cycle.extrapolate(&metrics, target_steps);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(i);
+            }
+        }
+        step(state);
+        metrics.push(metric(state));
+    }
+    metrics[target_steps]
+}
+
+/// Finds the cycle of the sequence starting at `init` with Brent's algorithm,
+/// comparing states through `state_key`. Gives up with `None` if no cycle is found
+/// within `max_steps` steps of the sequence.
+pub fn brent<S, K>(
+    init: &S,
+    state_key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+    max_steps: usize,
+) -> Option<Cycle>
+where
+    S: Clone,
+    K: Eq,
+{
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = init.clone();
+    let mut hare = init.clone();
+    step(&mut hare);
+    let mut hare_steps = 1;
+    while state_key(&tortoise) != state_key(&hare) {
+        if hare_steps >= max_steps {
+            return None;
+        }
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        step(&mut hare);
+        hare_steps += 1;
+        period += 1;
+    }
+
+    let mut tortoise = init.clone();
+    let mut hare = init.clone();
+    for _ in 0..period {
+        step(&mut hare);
+    }
+    let mut start = 0;
+    while state_key(&tortoise) != state_key(&hare) {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+    Some(Cycle { start, period })
+}
+
+/// Finds the cycle of the sequence starting at `init` with Floyd's algorithm,
+/// comparing states through `state_key`. Gives up with `None` if no cycle is found
+/// within `max_steps` steps of the sequence.
+pub fn floyd<S, K>(
+    init: &S,
+    state_key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+    max_steps: usize,
+) -> Option<Cycle>
+where
+    S: Clone,
+    K: Eq,
+{
+    let mut tortoise = init.clone();
+    let mut hare = init.clone();
+    let mut hare_steps = 0;
+    loop {
+        if hare_steps >= max_steps {
+            return None;
+        }
+        step(&mut tortoise);
+        step(&mut hare);
+        step(&mut hare);
+        hare_steps += 2;
+        if state_key(&tortoise) == state_key(&hare) {
+            break;
+        }
+    }
+
+    let mut start = 0;
+    let mut tortoise = init.clone();
+    while state_key(&tortoise) != state_key(&hare) {
+        step(&mut tortoise);
+        step(&mut hare);
+        start += 1;
+    }
+
+    let mut period = 1;
+    let mut hare = tortoise.clone();
+    step(&mut hare);
+    while state_key(&tortoise) != state_key(&hare) {
+        step(&mut hare);
+        period += 1;
+    }
+    Some(Cycle { start, period })
+}
+
+/// Steps `state` up to `target_steps` and extrapolates `metric` from `cycle` if there is one,
+/// else steps it `target_steps` times.
+fn skip_ahead_with<S>(
+    state: &mut S,
+    cycle: Option<Cycle>,
+    mut step: impl FnMut(&mut S),
+    metric: impl Fn(&S) -> usize,
+    target_steps: usize,
+) -> usize {
+    let Some(cycle) = cycle else {
+        (0..target_steps).for_each(|_| step(state));
+        return metric(state);
+    };
+    let n_steps = target_steps.min(cycle.start + cycle.period);
+    let mut metrics = vec![metric(state)];
+    for _ in 0..n_steps {
+        step(state);
+        metrics.push(metric(state));
+    }
+    cycle.extrapolate(&metrics, target_steps)
+}
+
+/// Same as `skip_ahead`, but detects the cycle with Brent's algorithm so that
+/// only a couple of states are kept in memory at once. Sequences without a cycle
+/// within `target_steps` steps are simply stepped through.
+pub fn skip_ahead_brent<S, K>(
+    state: &mut S,
+    state_key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+    metric: impl Fn(&S) -> usize,
+    target_steps: usize,
+) -> usize
+where
+    S: Clone,
+    K: Eq,
+{
+    let cycle = brent(state, state_key, &mut step, target_steps);
+    skip_ahead_with(state, cycle, step, metric, target_steps)
+}
+
+/// Same as `skip_ahead`, but detects the cycle with Floyd's algorithm so that
+/// only a couple of states are kept in memory at once. Sequences without a cycle
+/// within `target_steps` steps are simply stepped through.
+pub fn skip_ahead_floyd<S, K>(
+    state: &mut S,
+    state_key: impl Fn(&S) -> K,
+    mut step: impl FnMut(&mut S),
+    metric: impl Fn(&S) -> usize,
+    target_steps: usize,
+) -> usize
+where
+    S: Clone,
+    K: Eq,
+{
+    let cycle = floyd(state, state_key, &mut step, target_steps);
+    skip_ahead_with(state, cycle, step, metric, target_steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (value, running sum): value goes 0, 1, 2, 3, 4, 5, 6, 3, 4, 5, 6, 3, ...
+    fn step(state: &mut (usize, usize)) {
+        state.0 = if state.0 == 6 { 3 } else { state.0 + 1 };
+        state.1 += state.0;
+    }
+
+    fn brute_force(target_steps: usize) -> usize {
+        let mut state = (0, 0);
+        (0..target_steps).for_each(|_| step(&mut state));
+        state.1
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let expected = Cycle {
+            start: 3,
+            period: 4,
+        };
+        assert_eq!(brent(&(0, 0), |s| s.0, step, 100), Some(expected));
+        assert_eq!(floyd(&(0, 0), |s| s.0, step, 100), Some(expected));
+        assert_eq!(brent(&(0, 0), |s| s.0, step, 2), None);
+        assert_eq!(floyd(&(0, 0), |s| s.0, step, 2), None);
+    }
+
+    #[test]
+    fn test_no_cycle() {
+        // (n, sum of 1..=n) never repeats
+        let count = |state: &mut (usize, usize)| {
+            state.0 += 1;
+            state.1 += state.0;
+        };
+        assert_eq!(brent(&(0, 0), |s| s.0, count, 1_000), None);
+        assert_eq!(floyd(&(0, 0), |s| s.0, count, 1_000), None);
+        for target in [0, 1, 2, 100] {
+            let expected = target * (target + 1) / 2;
+            assert_eq!(
+                skip_ahead(&mut (0, 0), |s| s.0, count, |s| s.1, target),
+                expected
+            );
+            assert_eq!(
+                skip_ahead_brent(&mut (0, 0), |s| s.0, count, |s| s.1, target),
+                expected
+            );
+            assert_eq!(
+                skip_ahead_floyd(&mut (0, 0), |s| s.0, count, |s| s.1, target),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_skip_ahead() {
+        for target in [0, 2, 7, 10, 101, 1_000] {
+            let expected = brute_force(target);
+            assert_eq!(
+                skip_ahead(&mut (0, 0), |s| s.0, step, |s| s.1, target),
+                expected
+            );
+            assert_eq!(
+                skip_ahead_brent(&mut (0, 0), |s| s.0, step, |s| s.1, target),
+                expected
+            );
+            assert_eq!(
+                skip_ahead_floyd(&mut (0, 0), |s| s.0, step, |s| s.1, target),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use super::Interner;
+
+/// Distance used for pairs of nodes with no path between them.
+pub const UNREACHABLE: usize = usize::MAX;
+
+/// Unweighted directed graph whose nodes are identified by a label.
+/// Labels are mapped to dense indices in insertion order.
+#[derive(Debug, Clone)]
+pub struct LabelledGraph<L> {
+    nodes: Interner<L>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl<L> Default for LabelledGraph<L> {
+    fn default() -> Self {
+        LabelledGraph {
+            nodes: Interner::default(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<L> LabelledGraph<L>
+where
+    L: Copy + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of `label`, inserting it if it is not known yet.
+    pub fn add_node(&mut self, label: L) -> usize {
+        let idx = self.nodes.intern(label);
+        if idx == self.edges.len() {
+            self.edges.push(Vec::new());
+        }
+        idx
+    }
+
+    pub fn add_edge(&mut self, from: L, to: L) {
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        if !self.edges[from].contains(&to) {
+            self.edges[from].push(to);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn index_of(&self, label: &L) -> Option<usize> {
+        self.nodes.get(label)
+    }
+
+    pub fn label(&self, idx: usize) -> L {
+        *self.nodes.resolve(idx)
+    }
+
+    fn bfs(&self, from: usize) -> Vec<usize> {
+        let mut dists = vec![UNREACHABLE; self.len()];
+        dists[from] = 0;
+        let mut to_visit = VecDeque::from([from]);
+        while let Some(node) = to_visit.pop_front() {
+            for &neigh in &self.edges[node] {
+                if dists[neigh] != UNREACHABLE {
+                    continue;
+                }
+                dists[neigh] = dists[node] + 1;
+                to_visit.push_back(neigh);
+            }
+        }
+        dists
+    }
+
+    /// All-pairs shortest paths, computed with one BFS per node.
+    pub fn all_pairs_dists(&self) -> DistMatrix {
+        let n = self.len();
+        let mut dists = Vec::with_capacity(n * n);
+        for from in 0..n {
+            dists.extend(self.bfs(from));
+        }
+        DistMatrix { n, dists }
+    }
+
+    /// Keeps only the nodes for which `keep` is true, re-indexed densely in their
+    /// original order, with the shortest distances of the full graph between them.
+    pub fn compress(&self, keep: impl Fn(&L) -> bool) -> CompressedGraph<L> {
+        let all_dists = self.all_pairs_dists();
+        let kept: Vec<usize> = (0..self.len())
+            .filter(|&idx| keep(self.nodes.resolve(idx)))
+            .collect();
+        let n = kept.len();
+        let mut dists = Vec::with_capacity(n * n);
+        for &from in &kept {
+            dists.extend(kept.iter().map(|&to| all_dists.get(from, to)));
+        }
+        CompressedGraph {
+            labels: kept.iter().map(|&idx| self.label(idx)).collect(),
+            dists: DistMatrix { n, dists },
+        }
+    }
+}
+
+/// Dense `n * n` matrix of distances, indexed by node index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistMatrix {
+    n: usize,
+    dists: Vec<usize>,
+}
+
+impl DistMatrix {
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// Distance from `from` to `to`, or `UNREACHABLE`.
+    pub fn get(&self, from: usize, to: usize) -> usize {
+        self.dists[from * self.n + to]
+    }
+
+    pub fn row(&self, from: usize) -> &[usize] {
+        &self.dists[from * self.n..(from + 1) * self.n]
+    }
+}
+
+/// Subset of a `LabelledGraph` with the distances between the kept nodes.
+#[derive(Debug, Clone)]
+pub struct CompressedGraph<L> {
+    labels: Vec<L>,
+    dists: DistMatrix,
+}
+
+impl<L> CompressedGraph<L>
+where
+    L: Copy + Eq,
+{
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn index_of(&self, label: &L) -> Option<usize> {
+        self.labels.iter().position(|l| l == label)
+    }
+
+    pub fn label(&self, idx: usize) -> L {
+        self.labels[idx]
+    }
+
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    pub fn dist(&self, from: usize, to: usize) -> usize {
+        self.dists.get(from, to)
+    }
+
+    pub fn dists(&self) -> &DistMatrix {
+        &self.dists
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_graph() -> LabelledGraph<char> {
+        // a <-> b <-> c <-> d, e isolated
+        let mut graph = LabelledGraph::new();
+        for (from, to) in [('a', 'b'), ('b', 'c'), ('c', 'd')] {
+            graph.add_edge(from, to);
+            graph.add_edge(to, from);
+        }
+        graph.add_node('e');
+        graph
+    }
+
+    #[test]
+    fn test_all_pairs_dists() {
+        let graph = line_graph();
+        let dists = graph.all_pairs_dists();
+        assert_eq!(dists.len(), 5);
+        assert_eq!(dists.row(0), &[0, 1, 2, 3, UNREACHABLE]);
+        assert_eq!(dists.get(3, 1), 2);
+        assert_eq!(dists.get(4, 4), 0);
+    }
+
+    #[test]
+    fn test_compress() {
+        let graph = line_graph();
+        let compressed = graph.compress(|&l| l == 'a' || l == 'd' || l == 'e');
+        assert_eq!(compressed.labels(), &['a', 'd', 'e']);
+        assert_eq!(compressed.index_of(&'d'), Some(1));
+        assert_eq!(compressed.dist(0, 1), 3);
+        assert_eq!(compressed.dist(1, 0), 3);
+        assert_eq!(compressed.dist(0, 2), UNREACHABLE);
+    }
+}
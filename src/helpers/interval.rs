@@ -0,0 +1,250 @@
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+/// Integers usable as bounds of an `IntervalSet`.
+pub trait Discrete: Copy + Ord + Debug {
+    /// Next value, `None` at `MAX`.
+    fn succ(self) -> Option<Self>;
+    /// Previous value, `None` at `MIN`.
+    fn pred(self) -> Option<Self>;
+    /// Number of values in `start..=end`, `start <= end`.
+    fn count(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                fn succ(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+                fn pred(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+                fn count(start: Self, end: Self) -> u128 {
+                    (end as i128).abs_diff(start as i128) + 1
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(i32, i64, isize, u32, u64, usize);
+
+/// Set of integers stored as sorted, disjoint and non-adjacent inclusive ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every value of `range`, merging it with the ranges it overlaps or touches.
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        // first range that could touch the new one, and first one after it
+        let first = self
+            .ranges
+            .partition_point(|&(_, e)| e.succ().is_some_and(|after| after < start));
+        let last = self
+            .ranges
+            .partition_point(|&(s, _)| end.succ().is_none_or(|after| s <= after));
+        if first < last {
+            start = start.min(self.ranges[first].0);
+            end = end.max(self.ranges[last - 1].1);
+        }
+        self.ranges.splice(first..last, [(start, end)]);
+    }
+
+    /// Adds every value of `other`.
+    pub fn merge(&mut self, other: &IntervalSet<T>) {
+        for range in other.iter() {
+            self.insert(range);
+        }
+    }
+
+    /// Removes `point`, splitting the range containing it if needed.
+    pub fn remove(&mut self, point: T) {
+        let idx = self.ranges.partition_point(|&(_, e)| e < point);
+        let Some(&(start, end)) = self.ranges.get(idx) else {
+            return;
+        };
+        if point < start {
+            return;
+        }
+        let left = point.pred().map(|before| (start, before));
+        let right = point.succ().map(|after| (after, end));
+        self.ranges.splice(
+            idx..=idx,
+            [left, right].into_iter().flatten().filter(|&(s, e)| s <= e),
+        );
+    }
+
+    pub fn contains(&self, point: T) -> bool {
+        let idx = self.ranges.partition_point(|&(_, e)| e < point);
+        self.ranges.get(idx).is_some_and(|&(s, _)| s <= point)
+    }
+
+    /// Number of values in the set, exact even for a set of every `u64` or `i64`.
+    pub fn len(&self) -> u128 {
+        self.ranges.iter().map(|&(s, e)| T::count(s, e)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Values of `bounds` that are not in the set.
+    pub fn complement(&self, bounds: RangeInclusive<T>) -> IntervalSet<T> {
+        let (lo, hi) = bounds.into_inner();
+        let mut complement = IntervalSet::new();
+        let mut next = lo;
+        for &(s, e) in &self.ranges {
+            if e < lo {
+                continue;
+            }
+            if s > hi {
+                break;
+            }
+            if s > next {
+                // `s > next`, so `s` is not `MIN`
+                complement.ranges.push((next, s.pred().unwrap()));
+            }
+            match e.succ() {
+                Some(after) if e < hi => next = next.max(after),
+                _ => return complement,
+            }
+        }
+        if next <= hi {
+            complement.ranges.push((next, hi));
+        }
+        complement
+    }
+
+    /// Ranges of the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|&(s, e)| s..=e)
+    }
+}
+
+impl<T: Discrete> From<RangeInclusive<T>> for IntervalSet<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let mut set = IntervalSet::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        iter.into_iter().for_each(|range| set.insert(range));
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(set: &IntervalSet<i64>) -> Vec<RangeInclusive<i64>> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut set: IntervalSet<i64> = [10..=12, 0..=2, 5..=6].into_iter().collect();
+        assert_eq!(ranges(&set), [0..=2, 5..=6, 10..=12]);
+        set.insert(3..=4);
+        assert_eq!(ranges(&set), [0..=6, 10..=12]);
+        set.insert(-5..=20);
+        assert_eq!(ranges(&set), [-5..=20]);
+        let (start, end) = (3, 2);
+        set.insert(start..=end);
+        assert_eq!(set.len(), 26);
+
+        let mut other = IntervalSet::from(22..=25);
+        other.insert(21..=21);
+        set.merge(&other);
+        assert_eq!(ranges(&set), [-5..=25]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = IntervalSet::from(0..=10_i64);
+        set.remove(5);
+        set.remove(0);
+        set.remove(42);
+        assert_eq!(ranges(&set), [1..=4, 6..=10]);
+        assert_eq!(set.len(), 9);
+        assert!(set.contains(4) && !set.contains(5) && !set.contains(11));
+        set.remove(6);
+        set.remove(10);
+        assert_eq!(ranges(&set), [1..=4, 7..=9]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut set = IntervalSet::<usize>::from(0..=5);
+        set.remove(0);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1..=5]);
+        let mut set = IntervalSet::from(u32::MAX - 3..=u32::MAX);
+        set.remove(u32::MAX);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [u32::MAX - 3..=u32::MAX - 1]
+        );
+        set.remove(u32::MAX - 1);
+        set.insert(u32::MAX..=u32::MAX);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [u32::MAX - 3..=u32::MAX - 2, u32::MAX..=u32::MAX]
+        );
+        set.insert(u32::MAX - 1..=u32::MAX);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [u32::MAX - 3..=u32::MAX]);
+
+        assert_eq!(IntervalSet::from(0..=u64::MAX).len(), 1 << 64);
+        let mut set = IntervalSet::from(i64::MIN..=i64::MAX);
+        assert_eq!(set.len(), 1 << 64);
+        let mut split = set.clone();
+        split.remove(0);
+        assert_eq!(split.len(), (1 << 64) - 1);
+        set.remove(i64::MIN);
+        set.remove(i64::MAX);
+        assert_eq!(ranges(&set), [i64::MIN + 1..=i64::MAX - 1]);
+        set.insert(0..=i64::MAX);
+        assert_eq!(ranges(&set), [i64::MIN + 1..=i64::MAX]);
+        assert_eq!(
+            ranges(&set.complement(i64::MIN..=i64::MAX)),
+            [i64::MIN..=i64::MIN]
+        );
+        assert!(IntervalSet::from(0..=u64::MAX)
+            .complement(5..=u64::MAX)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_complement() {
+        let set: IntervalSet<i64> = [0..=2, 5..=6, 10..=12].into_iter().collect();
+        assert_eq!(ranges(&set.complement(0..=12)), [3..=4, 7..=9]);
+        assert_eq!(
+            ranges(&set.complement(-3..=20)),
+            [-3..=-1, 3..=4, 7..=9, 13..=20]
+        );
+        assert_eq!(ranges(&set.complement(6..=8)), [7..=8]);
+        assert!(set.complement(10..=11).is_empty());
+        assert_eq!(ranges(&IntervalSet::new().complement(1..=3)), [1..=3]);
+    }
+}
@@ -0,0 +1,98 @@
+/// Width of a glyph of the AoC font, letters are separated by one blank column.
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// Letters of the 4x6 font used by AoC puzzles that draw text, rows top to bottom.
+const GLYPHS: [(char, [&str; GLYPH_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Reads the letters drawn on `screen`, rows of `#` (lit) and `.` (dark).
+/// Returns `None` if the screen is not 6 rows high or a glyph is not a known letter.
+pub fn recognize(screen: &str) -> Option<String> {
+    let rows: Vec<&[u8]> = screen
+        .lines()
+        .map(str::as_bytes)
+        .filter(|row| !row.is_empty())
+        .collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+    let width = rows.iter().map(|row| row.len()).min()?;
+    (0..width)
+        .step_by(GLYPH_WIDTH + 1)
+        .map(|x| {
+            let glyph = rows
+                .iter()
+                .map(|row| row.get(x..x + GLYPH_WIDTH).unwrap_or(b"...."));
+            recognize_glyph(glyph)
+        })
+        .collect()
+}
+
+/// Draws `text` with the font, the way AoC screens do.
+/// Returns `None` if a letter has no glyph.
+pub fn draw(text: &str) -> Option<String> {
+    let glyphs: Vec<&[&str; GLYPH_HEIGHT]> = text
+        .chars()
+        .map(|c| GLYPHS.iter().find(|(l, _)| *l == c).map(|(_, rows)| rows))
+        .collect::<Option<_>>()?;
+    Some(
+        (0..GLYPH_HEIGHT)
+            .map(|y| {
+                glyphs
+                    .iter()
+                    .map(|rows| format!("{}.", rows[y]))
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect(),
+    )
+}
+
+fn recognize_glyph<'a>(glyph: impl Iterator<Item = &'a [u8]> + Clone) -> Option<char> {
+    GLYPHS
+        .iter()
+        .find(|(_, rows)| glyph.clone().eq(rows.iter().map(|row| row.as_bytes())))
+        .map(|&(letter, _)| letter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize() {
+        let letters: String = GLYPHS.iter().map(|(l, _)| l).collect();
+        assert_eq!(recognize(&draw(&letters).unwrap()), Some(letters));
+        assert_eq!(
+            recognize(&draw("RZHFGJCB").unwrap()),
+            Some("RZHFGJCB".to_string())
+        );
+        assert_eq!(draw("AM"), None);
+    }
+
+    #[test]
+    fn test_unknown_glyph() {
+        let mut screen = draw("AB").unwrap();
+        screen.replace_range(0..1, "#");
+        assert_eq!(recognize(&screen), None);
+        assert_eq!(recognize("####\n#...\n"), None);
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::Display;
+
+use nom::{combinator::all_consuming, error::ErrorKind, IResult};
+
+/// A group of consecutive non-blank lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// Line number of the first line, starting at 1.
+    pub line: usize,
+    /// The lines with their original line endings, without the whitespace ending the
+    /// last line. Whitespace ending the other lines is kept.
+    pub text: &'a str,
+}
+
+/// A nom failure within a record, located in the whole input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordError {
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: parsing failed ({:?})", self.line, self.kind)
+    }
+}
+
+impl<'a> Record<'a> {
+    /// Lines of the record, without line endings nor trailing whitespace.
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> {
+        self.text.lines().map(str::trim_end)
+    }
+
+    /// Runs `parser` over the whole record, which must consume it all. The parser sees
+    /// the `lines` joined with `\n`: trailing whitespace and CRLF line endings are removed.
+    pub fn parse<O>(&self, parser: impl FnMut(&str) -> IResult<&str, O>) -> Result<O, RecordError> {
+        let text = self.lines().collect::<Vec<_>>().join("\n");
+        let mut parser = all_consuming(parser);
+        parser(&text)
+            .map(|(_, output)| output)
+            .map_err(|err| match err {
+                nom::Err::Error(err) | nom::Err::Failure(err) => RecordError {
+                    line: self.line_of(&text, err.input),
+                    kind: err.code,
+                },
+                nom::Err::Incomplete(_) => RecordError {
+                    line: self.line,
+                    kind: ErrorKind::Complete,
+                },
+            })
+    }
+
+    /// Line number of `rest`, a suffix of `text`, the record's lines joined with `\n`.
+    fn line_of(&self, text: &str, rest: &str) -> usize {
+        let consumed = text.len() - rest.len();
+        self.line + text[..consumed].matches('\n').count()
+    }
+}
+
+/// Groups of lines separated by blank lines, whitespace-only lines included.
+pub struct Records<'a> {
+    rest: &'a str,
+    /// Line number of the first line of `rest`.
+    line: usize,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Record<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut start = None;
+        let mut end = 0;
+        let mut offset = 0;
+        for line in self.rest.split_inclusive('\n') {
+            let content = line.trim_end();
+            if content.is_empty() {
+                if start.is_some() {
+                    break;
+                }
+            } else {
+                start.get_or_insert((offset, self.line));
+                end = offset + content.len();
+            }
+            offset += line.len();
+            self.line += 1;
+        }
+        let text = &self.rest[..offset];
+        self.rest = &self.rest[offset..];
+        start.map(|(start, line)| Record {
+            line,
+            text: &text[start..end],
+        })
+    }
+}
+
+/// Splits `input` into its blank-line-separated records, tolerating CRLF line endings.
+pub fn records(input: &str) -> Records<'_> {
+    Records {
+        rest: input,
+        line: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::{
+        character::complete::{digit1, line_ending},
+        combinator::map_res,
+        multi::separated_list1,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let input = "\n1\n2 \n\n \t\n\n3\r\n4\r\n\r\n5\n";
+        let records: Vec<Record> = records(input).collect();
+        assert_eq!(
+            records,
+            [
+                Record {
+                    line: 2,
+                    text: "1\n2"
+                },
+                Record {
+                    line: 7,
+                    text: "3\r\n4"
+                },
+                Record {
+                    line: 10,
+                    text: "5"
+                },
+            ]
+        );
+        let lines: Vec<&str> = records[0].lines().chain(records[1].lines()).collect();
+        assert_eq!(lines, ["1", "2", "3", "4"]);
+        assert_eq!(super::records("").count(), 0);
+        assert_eq!(super::records(" \n\n").count(), 0);
+    }
+
+    #[test]
+    fn test_parse() {
+        fn numbers(input: &str) -> IResult<&str, Vec<u32>> {
+            separated_list1(line_ending, map_res(digit1, str::parse::<u32>))(input)
+        }
+        let input = "1\r\n2\n\n3\n4x\n5";
+        let parsed: Vec<_> = records(input).map(|r| r.parse(numbers)).collect();
+        assert_eq!(parsed[0], Ok(vec![1, 2]));
+        assert_eq!(
+            parsed[1],
+            Err(RecordError {
+                line: 5,
+                kind: ErrorKind::Eof
+            })
+        );
+        assert_eq!(
+            parsed[1].as_ref().unwrap_err().to_string(),
+            "line 5: parsing failed (Eof)"
+        );
+        // trailing whitespace is trimmed from every line
+        let parsed: Vec<_> = records("1 \n2 \r\n\n3\t\n4x \n")
+            .map(|r| r.parse(numbers))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                Ok(vec![1, 2]),
+                Err(RecordError {
+                    line: 5,
+                    kind: ErrorKind::Eof
+                })
+            ]
+        );
+        let record = records("1 \n2 \n").next().unwrap();
+        assert_eq!(record.lines().collect::<Vec<_>>(), ["1", "2"]);
+    }
+}
@@ -0,0 +1,212 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// The `N` greatest items inserted so far, kept in a min-heap so that each
+/// insert is O(log N). On ties, the items inserted first are kept.
+#[derive(Debug, Clone)]
+pub struct TopN<T, const N: usize> {
+    /// Items with their insertion rank: among equal items, the latest is the smallest.
+    heap: BinaryHeap<Reverse<(T, Reverse<usize>)>>,
+    inserted: usize,
+}
+
+impl<T: Ord, const N: usize> Default for TopN<T, N> {
+    fn default() -> Self {
+        TopN {
+            heap: BinaryHeap::with_capacity(N),
+            inserted: 0,
+        }
+    }
+}
+
+impl<T: Ord, const N: usize> TopN<T, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `item` if it is among the `N` greatest items so far,
+    /// evicting the smallest kept item when full.
+    pub fn insert(&mut self, item: T) {
+        let entry = Reverse((item, Reverse(self.inserted)));
+        self.inserted += 1;
+        if self.heap.len() < N {
+            self.heap.push(entry);
+        } else if let Some(mut min) = self.heap.peek_mut() {
+            if entry < *min {
+                *min = entry;
+            }
+        }
+    }
+
+    /// Smallest kept item.
+    pub fn min(&self) -> Option<&T> {
+        self.heap.peek().map(|entry| &entry.0 .0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Kept items, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.heap.iter().map(|entry| &entry.0 .0)
+    }
+
+    /// Kept items, greatest first, equal items in insertion order.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| entry.0 .0)
+            .collect()
+    }
+}
+
+impl<T, const N: usize> TopN<T, N> {
+    /// Top-N of items compared by `key`, which need not be `Ord` themselves:
+    /// `TopN::<_, 3>::by_key(|item: &Item| item.score)`.
+    pub fn by_key<K: Ord, F: Fn(&T) -> K>(key: F) -> TopNByKey<T, K, F, N> {
+        TopNByKey {
+            top: TopN::new(),
+            key,
+        }
+    }
+}
+
+impl<T: Ord, const N: usize> Extend<T> for TopN<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| self.insert(item));
+    }
+}
+
+impl<T: Ord, const N: usize> FromIterator<T> for TopN<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut top = Self::new();
+        top.extend(iter);
+        top
+    }
+}
+
+/// An item ordered by its key only.
+#[derive(Debug, Clone)]
+struct Keyed<K, T>(K, T);
+
+impl<K: Ord, T> PartialEq for Keyed<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Ord, T> Eq for Keyed<K, T> {}
+
+impl<K: Ord, T> PartialOrd for Keyed<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for Keyed<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// The `N` items with the greatest keys, see `TopN::by_key`.
+#[derive(Debug, Clone)]
+pub struct TopNByKey<T, K, F, const N: usize> {
+    top: TopN<Keyed<K, T>, N>,
+    key: F,
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K, const N: usize> TopNByKey<T, K, F, N> {
+    pub fn insert(&mut self, item: T) {
+        self.top.insert(Keyed((self.key)(&item), item));
+    }
+
+    pub fn len(&self) -> usize {
+        self.top.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top.is_empty()
+    }
+
+    /// Kept items, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.top.iter().map(|keyed| &keyed.1)
+    }
+
+    /// Kept items, greatest key first, equal keys in insertion order.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.top
+            .into_sorted_vec()
+            .into_iter()
+            .map(|keyed| keyed.1)
+            .collect()
+    }
+}
+
+impl<T, K: Ord, F: Fn(&T) -> K, const N: usize> Extend<T> for TopNByKey<T, K, F, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| self.insert(item));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::rng::Lcg;
+
+    #[test]
+    fn test_insert() {
+        let mut top = TopN::<_, 4>::new();
+        top.extend([2, 4, 7, 7]);
+        top.insert(8);
+        assert_eq!(top.clone().into_sorted_vec(), [8, 7, 7, 4]);
+        top.insert(5);
+        assert_eq!(top.clone().into_sorted_vec(), [8, 7, 7, 5]);
+        top.insert(1);
+        assert_eq!(top.min(), Some(&5));
+        assert_eq!(top.len(), 4);
+    }
+
+    #[test]
+    fn test_fewer_than_n() {
+        let top: TopN<_, 5> = [3, 1].into_iter().collect();
+        assert_eq!(top.into_sorted_vec(), [3, 1]);
+        assert!(TopN::<u8, 0>::from_iter([1, 2]).is_empty());
+    }
+
+    #[test]
+    fn test_against_sort() {
+        let mut rng = Lcg::new(7);
+        let values: Vec<u64> = (0..500).map(|_| rng.below(100)).collect();
+        let top: TopN<_, 10> = values.iter().copied().collect();
+        let mut sorted = values.clone();
+        sorted.sort_by(|a, b| b.cmp(a));
+        assert_eq!(top.into_sorted_vec(), sorted[..10]);
+    }
+
+    #[test]
+    fn test_by_key() {
+        // `String` is not `Copy`
+        let mut top = TopN::<_, 2>::by_key(|word: &String| word.len());
+        top.extend(["a", "ccc", "bb", "ddd", "eee"].map(String::from));
+        assert_eq!(top.len(), 2);
+        assert_eq!(top.into_sorted_vec(), ["ccc", "ddd"]);
+    }
+
+    #[test]
+    fn test_ties() {
+        let mut top = TopN::<_, 2>::by_key(|&(_, score): &(&str, u32)| score);
+        top.extend([("x1", 1), ("x2", 1), ("y", 2)]);
+        assert_eq!(top.into_sorted_vec(), [("y", 2), ("x1", 1)]);
+        let mut top = TopN::<_, 3>::by_key(|&(_, score): &(&str, u32)| score);
+        top.extend([("a", 1), ("b", 1), ("c", 1), ("d", 1)]);
+        assert_eq!(top.into_sorted_vec(), [("a", 1), ("b", 1), ("c", 1)]);
+    }
+}
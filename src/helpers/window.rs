@@ -0,0 +1,127 @@
+/// The last `N` bytes of a sequence, and whether they are all distinct.
+/// Each push is O(1), so scanning a sequence is O(len) whatever `N`.
+#[derive(Debug, Clone)]
+pub struct DistinctWindow<const N: usize> {
+    /// Occurrences of each byte value in the window.
+    counts: [usize; 256],
+    /// Ring buffer of the window, the oldest byte is at `pushed % N`.
+    window: [u8; N],
+    pushed: usize,
+    /// Number of byte values occurring more than once in the window.
+    duplicates: usize,
+}
+
+impl<const N: usize> Default for DistinctWindow<N> {
+    fn default() -> Self {
+        DistinctWindow {
+            counts: [0; 256],
+            window: [0; N],
+            pushed: 0,
+            duplicates: 0,
+        }
+    }
+}
+
+impl<const N: usize> DistinctWindow<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes `byte`, dropping the oldest byte once the window is full,
+    /// and returns whether the last `N` bytes are all distinct.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if N == 0 {
+            return true;
+        }
+        let slot = self.pushed % N;
+        if self.pushed >= N {
+            let old = self.window[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.duplicates -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.duplicates += 1;
+        }
+        self.pushed += 1;
+        self.is_distinct()
+    }
+
+    /// Whether at least `N` bytes were pushed and the last `N` are all distinct.
+    pub fn is_distinct(&self) -> bool {
+        self.pushed >= N && self.duplicates == 0
+    }
+
+    /// Number of bytes pushed so far.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    /// Number of bytes read up to the end of the first `N` distinct bytes in a row.
+    pub fn find_in(bytes: impl IntoIterator<Item = u8>) -> Option<usize> {
+        let mut window = Self::new();
+        bytes
+            .into_iter()
+            .position(|byte| window.push(byte))
+            .map(|index| index + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::rng::Lcg;
+
+    fn naive<const N: usize>(bytes: &[u8]) -> Option<usize> {
+        bytes
+            .windows(N)
+            .position(|w| (0..N).all(|i| !w[i + 1..].contains(&w[i])))
+            .map(|index| index + N)
+    }
+
+    /// `len` pseudo-random bytes among the first `alphabet` lowercase letters.
+    fn random_bytes(seed: u64, len: usize, alphabet: u64) -> Vec<u8> {
+        let mut rng = Lcg::new(seed);
+        (0..len).map(|_| b'a' + rng.below(alphabet) as u8).collect()
+    }
+
+    fn check_against_naive<const N: usize>() {
+        for seed in 0..200 {
+            let alphabet = N as u64 + seed % 8;
+            let bytes = random_bytes(seed, (seed as usize * 7) % 300, alphabet);
+            assert_eq!(
+                DistinctWindow::<N>::find_in(bytes.iter().copied()),
+                naive::<N>(&bytes),
+                "N = {N}, input {:?}",
+                String::from_utf8_lossy(&bytes)
+            );
+        }
+    }
+
+    #[test]
+    fn test_against_naive() {
+        check_against_naive::<1>();
+        check_against_naive::<2>();
+        check_against_naive::<4>();
+        check_against_naive::<7>();
+        check_against_naive::<14>();
+        check_against_naive::<26>();
+    }
+
+    #[test]
+    fn test_push() {
+        let mut window = DistinctWindow::<3>::new();
+        assert!(!window.push(b'a'));
+        assert!(!window.push(b'b'));
+        assert!(window.push(b'c'));
+        assert!(!window.push(b'b'));
+        assert!(window.push(b'd'));
+        assert!(!window.push(b'd'));
+        assert!(!window.push(b'a'));
+        assert!(window.push(b'b'));
+        assert_eq!(window.pushed(), 8);
+    }
+}
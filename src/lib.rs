@@ -3,18 +3,28 @@
  * There is no need to edit this file unless you want to change template functionality.
  * Prefer `./helpers.rs` if you want to extract code from your solutions.
  */
+use std::collections::HashMap;
 use std::env;
+use std::fmt::{Debug, Display};
 use std::fs;
+use std::ops::RangeBounds;
+use std::process;
+use std::str::FromStr;
 
 pub mod helpers;
 
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
+pub const ANSI_CLEAR: &str = "\x1b[2J\x1b[H";
 
 #[macro_export]
 macro_rules! solve {
-    ($part:expr, $solver:ident, $input:expr) => {{
+    ($part:expr, $solver:ident, $input:expr, $params:expr) => {{
+        let params = $params;
+        advent_of_code::solve!($part, |input: &str| $solver(input, params), $input)
+    }};
+    ($part:expr, $solver:expr, $input:expr) => {{
         use advent_of_code::{ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
         use std::fmt::Display;
         use std::time::Instant;
@@ -50,6 +60,126 @@ pub fn read_file(folder: &str, day: u8) -> String {
     f.expect("could not open input file")
 }
 
+/// Values that differ between the example and the real puzzle (row numbers, round counts...),
+/// read from `src/<folder>/<day>.params` as `key = value` lines. Lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    values: HashMap<String, String>,
+    /// Keys the solution reads, any key is accepted when `None`.
+    known: Option<&'static [&'static str]>,
+}
+
+impl Params {
+    /// Value of `key`, or `default` if it is not set.
+    /// Panics if `key` is not one of the known keys, to catch typos in the solution.
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> T {
+        if let Some(known) = self.known {
+            assert!(
+                known.contains(&key),
+                "param {key} is not one of the known params: {}",
+                known.join(", ")
+            );
+        }
+        match self.values.get(key) {
+            Some(value) => value
+                .parse()
+                .unwrap_or_else(|_| panic!("invalid value for param {key}: {value}")),
+            None => default,
+        }
+    }
+
+    /// These params, restricted to the `known` keys: fails on the first other key,
+    /// to catch typos in params files.
+    pub fn with_known(self, known: &'static [&'static str]) -> Result<Self, String> {
+        let mut unknown: Vec<&String> = self
+            .values
+            .keys()
+            .filter(|key| !known.contains(&key.as_str()))
+            .collect();
+        unknown.sort();
+        match unknown.first() {
+            Some(key) => Err(format!(
+                "unknown param {key}, expected one of: {}",
+                known.join(", ")
+            )),
+            None => Ok(Params {
+                known: Some(known),
+                ..self
+            }),
+        }
+    }
+}
+
+impl From<&str> for Params {
+    fn from(value: &str) -> Self {
+        let values = value
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let (key, value) = l
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("invalid param line: {l}"));
+                (key.trim().to_string(), value.trim().to_string())
+            })
+            .collect();
+        Params {
+            values,
+            known: None,
+        }
+    }
+}
+
+/// Params of a day, empty if there is no params file: solutions default to the real puzzle values.
+/// Panics if the file sets a key that is not among `known`.
+pub fn read_params(folder: &str, day: u8, known: &'static [&'static str]) -> Params {
+    let cwd = env::current_dir().unwrap();
+
+    let filepath = cwd
+        .join("src")
+        .join(folder)
+        .join(format!("{day:02}.params"));
+
+    fs::read_to_string(&filepath)
+        .map(|f| Params::from(f.as_str()))
+        .unwrap_or_default()
+        .with_known(known)
+        .unwrap_or_else(|err| panic!("{err} in {}", filepath.display()))
+}
+
+/// Value of the `key` command line flag, `None` if it is not given.
+/// Exits with a usage error if the value does not parse.
+pub fn opt_arg<T>(args: &mut pico_args::Arguments, key: &'static str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    args.opt_value_from_str(key).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        process::exit(1);
+    })
+}
+
+/// Value of the `key` numeric command line flag, `None` if it is not given.
+/// Exits with a usage error if the value is not a number within `range`.
+pub fn opt_arg_in<T>(
+    args: &mut pico_args::Arguments,
+    key: &'static str,
+    range: impl RangeBounds<T> + Debug,
+) -> Option<T>
+where
+    T: FromStr + PartialOrd,
+    T::Err: Display,
+{
+    match args.opt_value_from_str::<_, T>(key) {
+        Ok(value) if value.as_ref().is_none_or(|v| range.contains(v)) => value,
+        _ => {
+            eprintln!("{key} must be an integer in {range:?}");
+            process::exit(1);
+        }
+    }
+}
+
 fn parse_time(val: &str, postfix: &str) -> f64 {
     val.split(postfix).next().unwrap().parse().unwrap()
 }
@@ -95,6 +225,43 @@ macro_rules! assert_approx_eq {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_params() {
+        let params = Params::from("# example values\nrow = 10\n\nname=abc\n");
+        assert_eq!(params.get("row", 2_000_000), 10);
+        assert_eq!(params.get("limit", 4_000_000), 4_000_000);
+        assert_eq!(params.get("name", String::new()), "abc");
+        assert_eq!(read_params("examples", 0, &["row"]).get("row", 3), 3);
+    }
+
+    #[test]
+    fn test_params_known() {
+        let params = Params::from(
+            "rwo = 10
+limit = 20",
+        );
+        assert_eq!(
+            params.clone().with_known(&["row", "limit"]),
+            Err("unknown param rwo, expected one of: row, limit".to_string())
+        );
+        let params = params.with_known(&["rwo", "limit"]).unwrap();
+        assert_eq!(params.get("rwo", 0), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "param row is not one of the known params: rwo, limit")]
+    fn test_params_unknown_get() {
+        let params = Params::from("").with_known(&["rwo", "limit"]).unwrap();
+        params.get("row", 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "param row is not one of the known params")]
+    fn test_params_no_known_get() {
+        let params = Params::from("").with_known(&[]).unwrap();
+        params.get("row", 0);
+    }
+
     #[test]
     fn test_parse_exec_time() {
         assert_approx_eq!(